@@ -0,0 +1,235 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{command, State};
+
+use crate::message::{CommitTemplate, LintRules};
+
+const STATE_FILE: &str = "app-state.json";
+// Pinned repositories are kept regardless of this limit
+const MAX_RECENT: usize = 20;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecentRepository {
+    path: String,
+    name: String,
+    pinned: bool,
+    last_opened: String,
+    last_branch: Option<String>,
+    layout: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AppData {
+    #[serde(default)]
+    recent: Vec<RecentRepository>,
+    #[serde(default)]
+    commit_templates: Vec<CommitTemplate>,
+    #[serde(default)]
+    lint_rules: LintRules,
+}
+
+// Backend-owned app state persisted as JSON in the app data directory
+pub struct AppStore {
+    file: Option<PathBuf>,
+    data: Mutex<AppData>,
+}
+
+impl AppStore {
+    // A missing file just means starting over with no history. One that cannot be parsed, e.g.
+    // from a newer version, is moved aside first so the next save does not destroy it.
+    pub fn load(dir: Option<PathBuf>) -> Self {
+        let file = dir.map(|dir| dir.join(STATE_FILE));
+        let data = match file.as_ref().map(|file| (file, fs::read_to_string(file))) {
+            Some((file, Ok(text))) => serde_json::from_str(&text).unwrap_or_else(|_| {
+                let _ = fs::rename(file, file.with_extension("json.bak"));
+                AppData::default()
+            }),
+            Some((file, Err(e))) if e.kind() != io::ErrorKind::NotFound => {
+                let _ = fs::rename(file, file.with_extension("json.bak"));
+                AppData::default()
+            }
+            _ => AppData::default(),
+        };
+
+        AppStore {
+            file,
+            data: Mutex::new(data),
+        }
+    }
+
+    pub fn record_open(&self, path: &str, branch: &str) -> Result<(), String> {
+        let path = normalize(path);
+        let mut data = self.data.lock().unwrap();
+
+        let mut entry = match data.recent.iter().position(|r| r.path == path) {
+            Some(index) => data.recent.remove(index),
+            None => new_entry(&path),
+        };
+        entry.last_opened = Utc::now().to_rfc3339();
+        entry.last_branch = Some(branch.to_string());
+        data.recent.insert(0, entry);
+
+        // Drop the oldest unpinned entries once the list is full
+        let mut unpinned = 0;
+        data.recent.retain(|r| {
+            if r.pinned {
+                return true;
+            }
+            unpinned += 1;
+            unpinned <= MAX_RECENT
+        });
+
+        self.save(&data)
+    }
+
+    fn update<F>(&self, path: &str, f: F) -> Result<(), String>
+    where
+        F: FnOnce(&mut Vec<RecentRepository>, &str),
+    {
+        let path = normalize(path);
+        let mut data = self.data.lock().unwrap();
+        f(&mut data.recent, &path);
+        self.save(&data)
+    }
+
+    pub fn commit_templates(&self) -> Vec<CommitTemplate> {
+        self.data.lock().unwrap().commit_templates.clone()
+    }
+
+    // Saving under an existing name replaces that template
+    pub fn save_commit_template(&self, template: CommitTemplate) -> Result<(), String> {
+        let mut data = self.data.lock().unwrap();
+        match data
+            .commit_templates
+            .iter_mut()
+            .find(|t| t.name == template.name)
+        {
+            Some(existing) => *existing = template,
+            None => data.commit_templates.push(template),
+        }
+        self.save(&data)
+    }
+
+    pub fn remove_commit_template(&self, name: &str) -> Result<(), String> {
+        let mut data = self.data.lock().unwrap();
+        data.commit_templates.retain(|t| t.name != name);
+        self.save(&data)
+    }
+
+    pub fn lint_rules(&self) -> LintRules {
+        self.data.lock().unwrap().lint_rules.clone()
+    }
+
+    pub fn set_lint_rules(&self, rules: LintRules) -> Result<(), String> {
+        let mut data = self.data.lock().unwrap();
+        data.lint_rules = rules;
+        self.save(&data)
+    }
+
+    // Written to a temporary file first so a crash mid-write cannot corrupt the history
+    fn save(&self, data: &AppData) -> Result<(), String> {
+        let file = match &self.file {
+            Some(file) => file,
+            None => return Ok(()),
+        };
+        if let Some(dir) = file.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+
+        let text = serde_json::to_string_pretty(data).map_err(|e| e.to_string())?;
+        let tmp = file.with_extension("json.tmp");
+        fs::write(&tmp, text).map_err(|e| e.to_string())?;
+        fs::rename(&tmp, file).map_err(|e| e.to_string())
+    }
+}
+
+#[command]
+pub async fn get_recent_repositories(
+    store: State<'_, AppStore>,
+) -> Result<Vec<RecentRepository>, String> {
+    let data = store.data.lock().unwrap();
+    let mut recent = data.recent.clone();
+    // Pinned first; the rest keep their most-recently-opened order
+    recent.sort_by_key(|r| !r.pinned);
+    Ok(recent)
+}
+
+#[command]
+pub async fn pin_repository(
+    path: String,
+    pinned: Option<bool>,
+    store: State<'_, AppStore>,
+) -> Result<(), String> {
+    let pinned = pinned.unwrap_or(true);
+    store.update(&path, |recent, path| {
+        match recent.iter_mut().find(|r| r.path == path) {
+            Some(entry) => entry.pinned = pinned,
+            None if pinned => {
+                let mut entry = new_entry(path);
+                entry.pinned = true;
+                recent.push(entry);
+            }
+            None => {}
+        }
+    })
+}
+
+#[command]
+pub async fn remove_recent(path: String, store: State<'_, AppStore>) -> Result<(), String> {
+    store.update(&path, |recent, path| recent.retain(|r| r.path != path))
+}
+
+// Layout hints are opaque to the backend; the frontend decides what to keep in them
+#[command]
+pub async fn save_repository_layout(
+    path: String,
+    layout: serde_json::Value,
+    store: State<'_, AppStore>,
+) -> Result<(), String> {
+    store.update(&path, |recent, path| {
+        if let Some(entry) = recent.iter_mut().find(|r| r.path == path) {
+            entry.layout = Some(layout);
+        }
+    })
+}
+
+fn new_entry(path: &str) -> RecentRepository {
+    let name = Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string());
+
+    RecentRepository {
+        path: path.to_string(),
+        name,
+        pinned: false,
+        last_opened: Utc::now().to_rfc3339(),
+        last_branch: None,
+        layout: None,
+    }
+}
+
+// The same repository picked as `repo/`, `./repo` or through a symlink should not show up
+// twice. Paths that no longer exist cannot be resolved and are only trimmed, which still
+// matches the resolved form they were stored under.
+fn normalize(path: &str) -> String {
+    if let Ok(resolved) = fs::canonicalize(path) {
+        let resolved = resolved.to_string_lossy().to_string();
+        // Windows hands back `\\?\C:\...`; the plain form is what users recognise
+        return match resolved.strip_prefix(r"\\?\") {
+            Some(plain) if !plain.starts_with("UNC") => plain.to_string(),
+            _ => resolved,
+        };
+    }
+
+    let trimmed = path.trim_end_matches(['/', '\\']);
+    if trimmed.is_empty() {
+        path.to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
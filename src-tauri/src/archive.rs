@@ -0,0 +1,460 @@
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use git2::{ObjectType, Repository, Tree};
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use tauri::{command, State};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::{resolve_revision, RepositoryState};
+
+const SYMLINK_MODE: i32 = 0o120000;
+const EXECUTABLE_MODE: i32 = 0o100755;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveResult {
+    output_path: String,
+    format: String,
+    files: usize,
+    excluded: usize,
+}
+
+// A single `export-ignore` rule read from a `.gitattributes` file
+struct AttrRule {
+    base: String,
+    pattern: Pattern,
+    basename_only: bool,
+    dir_only: bool,
+    ignore: bool,
+}
+
+enum ArchiveWriter {
+    Zip(ZipWriter<File>),
+    TarGz(tar::Builder<GzEncoder<File>>),
+}
+
+struct Archiver<'r> {
+    repo: &'r Repository,
+    writer: ArchiveWriter,
+    prefix: String,
+    mtime: DateTime<Utc>,
+    rules: Vec<AttrRule>,
+    info_rules: Vec<AttrRule>,
+    files: usize,
+    excluded: usize,
+}
+
+#[command]
+pub async fn export_archive(
+    rev: String,
+    format: String,
+    output_path: String,
+    prefix: Option<String>,
+    state: State<'_, RepositoryState>,
+) -> Result<ArchiveResult, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let object = resolve_revision(repo, &rev)?;
+    let tree = object.peel_to_tree().map_err(|e| e.to_string())?;
+    // Like `git archive`, entries carry the commit time rather than the time of export
+    let mtime = object
+        .peel_to_commit()
+        .ok()
+        .and_then(|commit| DateTime::from_timestamp(commit.time().seconds(), 0))
+        .unwrap_or_else(Utc::now);
+
+    // Checked before the output file is created so a bad format cannot clobber an existing file
+    let gzip = match format.as_str() {
+        "zip" => false,
+        "tar.gz" | "tgz" => true,
+        other => return Err(format!("Unsupported archive format: {}", other)),
+    };
+    let file = File::create(&output_path)
+        .map_err(|e| format!("Failed to create {}: {}", output_path, e))?;
+    let writer = if gzip {
+        ArchiveWriter::TarGz(tar::Builder::new(GzEncoder::new(
+            file,
+            Compression::default(),
+        )))
+    } else {
+        ArchiveWriter::Zip(ZipWriter::new(file))
+    };
+
+    let mut prefix = prefix
+        .unwrap_or_default()
+        .trim_start_matches('/')
+        .to_string();
+    if !prefix.is_empty() && !prefix.ends_with('/') {
+        prefix.push('/');
+    }
+
+    // `$GIT_DIR/info/attributes` takes precedence over every in-tree file
+    let info_rules = fs::read_to_string(repo.path().join("info").join("attributes"))
+        .map(|text| parse_attributes(&text, ""))
+        .unwrap_or_default();
+
+    let mut archiver = Archiver {
+        repo,
+        writer,
+        prefix,
+        mtime,
+        rules: Vec::new(),
+        info_rules,
+        files: 0,
+        excluded: 0,
+    };
+
+    let (files, excluded) = match archiver.add_tree(&tree, "") {
+        Ok(()) => (archiver.files, archiver.excluded),
+        Err(e) => {
+            let _ = fs::remove_file(&output_path);
+            return Err(format!("Failed to write archive: {}", e));
+        }
+    };
+    if let Err(e) = archiver.writer.finish() {
+        let _ = fs::remove_file(&output_path);
+        return Err(format!("Failed to write archive: {}", e));
+    }
+
+    Ok(ArchiveResult {
+        output_path,
+        format,
+        files,
+        excluded,
+    })
+}
+
+impl<'r> Archiver<'r> {
+    fn add_tree(&mut self, tree: &Tree, dir: &str) -> io::Result<()> {
+        let rule_count = self.rules.len();
+        if let Some(entry) = tree.get_name(".gitattributes") {
+            if let Ok(blob) = self.repo.find_blob(entry.id()) {
+                let text = String::from_utf8_lossy(blob.content());
+                self.rules.extend(parse_attributes(&text, dir));
+            }
+        }
+
+        for entry in tree.iter() {
+            let name = entry.name().unwrap_or("");
+            let path = format!("{}{}", dir, name);
+            let is_dir = entry.kind() != Some(ObjectType::Blob);
+
+            if self.is_export_ignored(&path, is_dir) {
+                self.excluded += 1;
+                continue;
+            }
+
+            match entry.kind() {
+                Some(ObjectType::Tree) => {
+                    let subtree = self.repo.find_tree(entry.id()).map_err(to_io)?;
+                    self.writer
+                        .add_dir(&self.archive_path(&path, true), self.mtime)?;
+                    self.add_tree(&subtree, &format!("{}/", path))?;
+                }
+                Some(ObjectType::Blob) => {
+                    let blob = self.repo.find_blob(entry.id()).map_err(to_io)?;
+                    let archive_path = self.archive_path(&path, false);
+                    if entry.filemode() == SYMLINK_MODE {
+                        let target = String::from_utf8_lossy(blob.content()).to_string();
+                        self.writer
+                            .add_symlink(&archive_path, &target, self.mtime)?;
+                    } else {
+                        let mode = if entry.filemode() == EXECUTABLE_MODE {
+                            0o755
+                        } else {
+                            0o644
+                        };
+                        self.writer
+                            .add_file(&archive_path, mode, blob.content(), self.mtime)?;
+                    }
+                    self.files += 1;
+                }
+                // Submodules show up as empty directories, matching `git archive`
+                Some(ObjectType::Commit) => {
+                    self.writer
+                        .add_dir(&self.archive_path(&path, true), self.mtime)?;
+                }
+                _ => {}
+            }
+        }
+
+        self.rules.truncate(rule_count);
+        Ok(())
+    }
+
+    fn archive_path(&self, path: &str, is_dir: bool) -> String {
+        format!("{}{}{}", self.prefix, path, if is_dir { "/" } else { "" })
+    }
+
+    fn is_export_ignored(&self, path: &str, is_dir: bool) -> bool {
+        export_ignored(&self.rules, &self.info_rules, path, is_dir)
+    }
+}
+
+impl ArchiveWriter {
+    fn add_file(
+        &mut self,
+        path: &str,
+        mode: u32,
+        data: &[u8],
+        mtime: DateTime<Utc>,
+    ) -> io::Result<()> {
+        match self {
+            ArchiveWriter::Zip(zip) => {
+                let options = zip_options(mtime)
+                    .compression_method(CompressionMethod::Deflated)
+                    .unix_permissions(mode);
+                zip.start_file(path, options).map_err(to_io)?;
+                zip.write_all(data)
+            }
+            ArchiveWriter::TarGz(tar) => {
+                let mut header = tar_header(tar::EntryType::Regular, mode, mtime);
+                header.set_size(data.len() as u64);
+                tar.append_data(&mut header, path, data)
+            }
+        }
+    }
+
+    fn add_symlink(&mut self, path: &str, target: &str, mtime: DateTime<Utc>) -> io::Result<()> {
+        match self {
+            ArchiveWriter::Zip(zip) => zip
+                .add_symlink(path, target, zip_options(mtime))
+                .map_err(to_io),
+            ArchiveWriter::TarGz(tar) => {
+                let mut header = tar_header(tar::EntryType::Symlink, 0o777, mtime);
+                tar.append_link(&mut header, path, target)
+            }
+        }
+    }
+
+    fn add_dir(&mut self, path: &str, mtime: DateTime<Utc>) -> io::Result<()> {
+        match self {
+            ArchiveWriter::Zip(zip) => zip
+                .add_directory(path, zip_options(mtime).unix_permissions(0o755))
+                .map_err(to_io),
+            ArchiveWriter::TarGz(tar) => {
+                let mut header = tar_header(tar::EntryType::Directory, 0o755, mtime);
+                tar.append_data(&mut header, path, io::empty())
+            }
+        }
+    }
+
+    fn finish(self) -> io::Result<()> {
+        match self {
+            ArchiveWriter::Zip(mut zip) => zip.finish().map(|_| ()).map_err(to_io),
+            ArchiveWriter::TarGz(tar) => tar.into_inner()?.finish().map(|_| ()),
+        }
+    }
+}
+
+fn zip_options(mtime: DateTime<Utc>) -> FileOptions {
+    let options = FileOptions::default();
+    // Zip timestamps cannot represent anything before 1980
+    match zip::DateTime::from_date_and_time(
+        mtime.year() as u16,
+        mtime.month() as u8,
+        mtime.day() as u8,
+        mtime.hour() as u8,
+        mtime.minute() as u8,
+        mtime.second() as u8,
+    ) {
+        Ok(time) => options.last_modified_time(time),
+        Err(_) => options,
+    }
+}
+
+fn tar_header(entry_type: tar::EntryType, mode: u32, mtime: DateTime<Utc>) -> tar::Header {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(entry_type);
+    header.set_mode(mode);
+    header.set_mtime(mtime.timestamp().max(0) as u64);
+    header.set_size(0);
+    header
+}
+
+fn parse_attributes(text: &str, base: &str) -> Vec<AttrRule> {
+    let mut rules = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut parts = line.split_whitespace();
+        let raw_pattern = match parts.next() {
+            Some(pattern) => pattern,
+            None => continue,
+        };
+        let ignore = match parts.find_map(|attr| match attr {
+            "export-ignore" => Some(true),
+            "-export-ignore" | "!export-ignore" => Some(false),
+            a if a.starts_with("export-ignore=") => Some(true),
+            _ => None,
+        }) {
+            Some(ignore) => ignore,
+            None => continue,
+        };
+
+        let dir_only = raw_pattern.ends_with('/');
+        let trimmed = raw_pattern.trim_end_matches('/');
+        // A pattern with no slash matches a name at any depth, otherwise it is relative to `base`
+        let basename_only = !trimmed.contains('/');
+        let pattern = match Pattern::new(trimmed.trim_start_matches('/')) {
+            Ok(pattern) => pattern,
+            Err(_) => continue,
+        };
+
+        rules.push(AttrRule {
+            base: base.to_string(),
+            pattern,
+            basename_only,
+            dir_only,
+            ignore,
+        });
+    }
+
+    rules
+}
+
+// Later rules win, so deeper `.gitattributes` files override their parents and
+// `info/attributes` overrides them all
+fn export_ignored(rules: &[AttrRule], info_rules: &[AttrRule], path: &str, is_dir: bool) -> bool {
+    rules
+        .iter()
+        .chain(info_rules.iter())
+        .rev()
+        .find(|rule| rule_matches(rule, path, is_dir))
+        .map(|rule| rule.ignore)
+        .unwrap_or(false)
+}
+
+fn rule_matches(rule: &AttrRule, path: &str, is_dir: bool) -> bool {
+    if rule.dir_only && !is_dir {
+        return false;
+    }
+    let relative = match path.strip_prefix(rule.base.as_str()) {
+        Some(relative) => relative,
+        None => return false,
+    };
+
+    let options = MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    };
+    if rule.basename_only {
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        rule.pattern.matches_with(name, options)
+    } else {
+        rule.pattern.matches_with(relative, options)
+    }
+}
+
+fn to_io<E: std::fmt::Display>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ignored(rules: &[AttrRule], path: &str, is_dir: bool) -> bool {
+        export_ignored(rules, &[], path, is_dir)
+    }
+
+    #[test]
+    fn reads_only_export_ignore_rules() {
+        let rules = parse_attributes(
+            "# comment\n\n*.png binary\n*.log export-ignore\nkeep.log -export-ignore\n\
+             old.log !export-ignore\ntmp/ export-ignore=yes\n",
+            "",
+        );
+        let ignores: Vec<(&str, bool)> = rules
+            .iter()
+            .map(|rule| (rule.pattern.as_str(), rule.ignore))
+            .collect();
+        assert_eq!(
+            ignores,
+            vec![
+                ("*.log", true),
+                ("keep.log", false),
+                ("old.log", false),
+                ("tmp", true)
+            ]
+        );
+    }
+
+    #[test]
+    fn patterns_without_a_slash_match_at_any_depth() {
+        let rules = parse_attributes("*.log export-ignore\n", "");
+        assert!(ignored(&rules, "debug.log", false));
+        assert!(ignored(&rules, "a/b/debug.log", false));
+        assert!(!ignored(&rules, "debug.log.txt", false));
+
+        // Rules from a nested `.gitattributes` only cover that directory
+        let rules = parse_attributes("*.log export-ignore\n", "sub/");
+        assert!(ignored(&rules, "sub/x/debug.log", false));
+        assert!(!ignored(&rules, "debug.log", false));
+        assert!(!ignored(&rules, "other/debug.log", false));
+    }
+
+    #[test]
+    fn patterns_with_a_slash_are_anchored() {
+        let rules = parse_attributes("/build export-ignore\ndocs/*.md export-ignore\n", "");
+        assert!(ignored(&rules, "build", true));
+        assert!(!ignored(&rules, "src/build", true));
+        assert!(ignored(&rules, "docs/intro.md", false));
+        assert!(!ignored(&rules, "docs/guide/intro.md", false));
+        assert!(!ignored(&rules, "site/docs/intro.md", false));
+
+        let rules = parse_attributes("docs/*.md export-ignore\n", "site/");
+        assert!(ignored(&rules, "site/docs/intro.md", false));
+        assert!(!ignored(&rules, "docs/intro.md", false));
+    }
+
+    #[test]
+    fn double_star_crosses_directories() {
+        let rules = parse_attributes("**/fixtures export-ignore\nassets/** export-ignore\n", "");
+        assert!(ignored(&rules, "fixtures", true));
+        assert!(ignored(&rules, "tests/unit/fixtures", true));
+        assert!(ignored(&rules, "assets/img/logo.png", false));
+        assert!(!ignored(&rules, "src/assets.rs", false));
+    }
+
+    #[test]
+    fn trailing_slash_matches_only_directories() {
+        let rules = parse_attributes("target/ export-ignore\n", "");
+        assert!(ignored(&rules, "target", true));
+        assert!(ignored(&rules, "crates/a/target", true));
+        assert!(!ignored(&rules, "target", false));
+    }
+
+    #[test]
+    fn later_rules_override_earlier_ones() {
+        let mut rules = parse_attributes("*.txt export-ignore\nkeep.txt -export-ignore\n", "");
+        assert!(ignored(&rules, "notes.txt", false));
+        assert!(!ignored(&rules, "keep.txt", false));
+
+        // A deeper file is read later, so it overrides its parent
+        rules.extend(parse_attributes("*.txt -export-ignore\n", "docs/"));
+        assert!(!ignored(&rules, "docs/notes.txt", false));
+        assert!(ignored(&rules, "notes.txt", false));
+    }
+
+    #[test]
+    fn info_attributes_override_the_tree() {
+        let rules = parse_attributes("secret.txt -export-ignore\n", "docs/");
+        let info = parse_attributes("secret.txt export-ignore\n", "");
+        assert!(export_ignored(&rules, &info, "docs/secret.txt", false));
+
+        let info = parse_attributes("*.md -export-ignore\n", "");
+        let rules = parse_attributes("README.md export-ignore\n", "");
+        assert!(!export_ignored(&rules, &info, "README.md", false));
+    }
+}
@@ -0,0 +1,449 @@
+use git2::build::CheckoutBuilder;
+use git2::{Oid, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use tauri::{command, State};
+
+use crate::{resolve_commit, RepositoryState};
+
+// The same files and refs `git bisect` uses, so a session can be finished from either side
+const BISECT_START: &str = "BISECT_START";
+const BISECT_TERMS: &str = "BISECT_TERMS";
+const BISECT_LOG: &str = "BISECT_LOG";
+const BAD_REF: &str = "refs/bisect/bad";
+const GOOD_PREFIX: &str = "refs/bisect/good-";
+const SKIP_PREFIX: &str = "refs/bisect/skip-";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BisectCommit {
+    id: String,
+    summary: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct BisectStatus {
+    active: bool,
+    bad: Option<String>,
+    good: Vec<String>,
+    skipped: Vec<String>,
+    remaining: usize,
+    steps: u32,
+    candidate: Option<BisectCommit>,
+    first_bad: Option<BisectCommit>,
+}
+
+#[command]
+pub async fn bisect_start(
+    good: Vec<String>,
+    bad: String,
+    state: State<'_, RepositoryState>,
+) -> Result<BisectStatus, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    if is_active(repo) {
+        return Err("A bisect is already in progress; reset it first".to_string());
+    }
+    if good.is_empty() {
+        return Err("At least one good revision is required".to_string());
+    }
+
+    let bad = resolve_commit(repo, &bad)?;
+    let good = good
+        .iter()
+        .map(|rev| resolve_commit(repo, rev))
+        .collect::<Result<Vec<_>, String>>()?;
+    for oid in &good {
+        check_good_before_bad(repo, *oid, bad)?;
+    }
+
+    // Remember where HEAD was so reset can put it back
+    let head = repo.head().map_err(|e| e.to_string())?;
+    let start = if head.is_branch() {
+        head.shorthand().unwrap_or("HEAD").to_string()
+    } else {
+        head.target().map(|oid| oid.to_string()).unwrap_or_default()
+    };
+    write_state_file(repo, BISECT_START, &format!("{}\n", start))?;
+    write_state_file(repo, BISECT_TERMS, "bad\ngood\n")?;
+    write_state_file(repo, BISECT_LOG, "")?;
+
+    mark(repo, bad, "bad")?;
+    for oid in &good {
+        mark(repo, *oid, "good")?;
+    }
+    let args: Vec<String> = std::iter::once(bad)
+        .chain(good.iter().copied())
+        .map(|oid| format!("'{}'", oid))
+        .collect();
+    append_log(repo, &format!("git bisect start {}\n", args.join(" ")))?;
+
+    advance(repo)
+}
+
+#[command]
+pub async fn bisect_mark(
+    oid: Option<String>,
+    verdict: String,
+    state: State<'_, RepositoryState>,
+) -> Result<BisectStatus, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    if !is_active(repo) {
+        return Err("No bisect in progress".to_string());
+    }
+    if !matches!(verdict.as_str(), "good" | "bad" | "skip") {
+        return Err(format!("Unknown bisect verdict: {}", verdict));
+    }
+
+    // Marking without an oid means the commit currently checked out, like `git bisect good`
+    let oid = resolve_commit(repo, oid.as_deref().unwrap_or("HEAD"))?;
+    match verdict.as_str() {
+        "bad" => {
+            for good in ref_targets(repo, GOOD_PREFIX)? {
+                check_good_before_bad(repo, good, oid)?;
+            }
+        }
+        "good" => {
+            let bad = repo.refname_to_id(BAD_REF).map_err(|e| e.to_string())?;
+            check_good_before_bad(repo, oid, bad)?;
+        }
+        _ => {}
+    }
+    mark(repo, oid, &verdict)?;
+    append_log(repo, &format!("git bisect {} {}\n", verdict, oid))?;
+
+    advance(repo)
+}
+
+#[command]
+pub async fn bisect_status(state: State<'_, RepositoryState>) -> Result<BisectStatus, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    if !is_active(repo) {
+        return Ok(BisectStatus::default());
+    }
+    compute_status(repo)
+}
+
+#[command]
+pub async fn bisect_reset(state: State<'_, RepositoryState>) -> Result<(), String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    if !is_active(repo) {
+        return Ok(());
+    }
+
+    let start = fs::read_to_string(repo.path().join(BISECT_START)).map_err(|e| e.to_string())?;
+    let start = start.trim();
+    let branch = format!("refs/heads/{}", start);
+    if repo.find_reference(&branch).is_ok() {
+        let target = repo.revparse_single(&branch).map_err(|e| e.to_string())?;
+        repo.checkout_tree(&target, Some(CheckoutBuilder::new().safe()))
+            .map_err(|e| e.to_string())?;
+        repo.set_head(&branch).map_err(|e| e.to_string())?;
+    } else if let Ok(oid) = Oid::from_str(start) {
+        checkout_detached(repo, oid)?;
+    }
+
+    let refs: Vec<String> = repo
+        .references_glob("refs/bisect/*")
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .filter_map(|r| r.name().map(|n| n.to_string()))
+        .collect();
+    for name in refs {
+        if let Ok(mut reference) = repo.find_reference(&name) {
+            reference.delete().map_err(|e| e.to_string())?;
+        }
+    }
+    for file in [BISECT_START, BISECT_TERMS, BISECT_LOG] {
+        let _ = fs::remove_file(repo.path().join(file));
+    }
+
+    Ok(())
+}
+
+fn is_active(repo: &Repository) -> bool {
+    repo.path().join(BISECT_START).is_file()
+}
+
+fn mark(repo: &Repository, oid: Oid, verdict: &str) -> Result<(), String> {
+    let name = match verdict {
+        "bad" => BAD_REF.to_string(),
+        "good" => format!("{}{}", GOOD_PREFIX, oid),
+        _ => format!("{}{}", SKIP_PREFIX, oid),
+    };
+    repo.reference(&name, oid, true, &format!("bisect: mark {}", verdict))
+        .map_err(|e| e.to_string())?;
+
+    let summary = repo
+        .find_commit(oid)
+        .ok()
+        .and_then(|c| c.summary().map(|s| s.to_string()))
+        .unwrap_or_default();
+    append_log(repo, &format!("# {}: [{}] {}\n", verdict, oid, summary))
+}
+
+// Checks out the next commit to test, or leaves HEAD alone once the culprit is known
+fn advance(repo: &Repository) -> Result<BisectStatus, String> {
+    let status = compute_status(repo)?;
+    if let Some(candidate) = &status.candidate {
+        let oid = Oid::from_str(&candidate.id).map_err(|e| e.to_string())?;
+        checkout_detached(repo, oid)?;
+    } else if let Some(first_bad) = &status.first_bad {
+        append_log(
+            repo,
+            &format!(
+                "# first bad commit: [{}] {}\n",
+                first_bad.id, first_bad.summary
+            ),
+        )?;
+    }
+    Ok(status)
+}
+
+fn compute_status(repo: &Repository) -> Result<BisectStatus, String> {
+    let bad = repo
+        .refname_to_id(BAD_REF)
+        .map_err(|_| "Bisect has no bad commit".to_string())?;
+    let good = ref_targets(repo, GOOD_PREFIX)?;
+    let skipped = ref_targets(repo, SKIP_PREFIX)?;
+
+    // Candidates are everything reachable from bad that no good commit can reach,
+    // ordered parents first so weights can be built up in a single pass
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    revwalk
+        .set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)
+        .map_err(|e| e.to_string())?;
+    revwalk.push(bad).map_err(|e| e.to_string())?;
+    for oid in &good {
+        revwalk.hide(*oid).map_err(|e| e.to_string())?;
+    }
+    let candidates = revwalk
+        .collect::<Result<Vec<Oid>, _>>()
+        .map_err(|e| e.to_string())?;
+
+    let index: HashMap<Oid, usize> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, oid)| (*oid, i))
+        .collect();
+    let skipped_set: HashSet<Oid> = skipped.iter().copied().collect();
+    let mut parents = Vec::with_capacity(candidates.len());
+    for oid in &candidates {
+        let commit = repo.find_commit(*oid).map_err(|e| e.to_string())?;
+        let in_range: Vec<usize> = commit
+            .parent_ids()
+            .filter_map(|parent| index.get(&parent).copied())
+            .collect();
+        parents.push(in_range);
+    }
+    let is_skipped: Vec<bool> = candidates
+        .iter()
+        .map(|oid| skipped_set.contains(oid))
+        .collect();
+
+    let remaining = is_skipped.iter().filter(|skipped| !**skipped).count();
+    let (best, found) = select(&parents, &is_skipped);
+    let candidate = best.map(|i| to_commit(repo, candidates[i]));
+    let first_bad = if found {
+        Some(to_commit(repo, bad))
+    } else {
+        None
+    };
+
+    Ok(BisectStatus {
+        active: true,
+        bad: Some(bad.to_string()),
+        good: good.iter().map(|oid| oid.to_string()).collect(),
+        skipped: skipped.iter().map(|oid| oid.to_string()).collect(),
+        remaining,
+        steps: (usize::BITS - remaining.leading_zeros()).saturating_sub(1),
+        candidate,
+        first_bad,
+    })
+}
+
+// Git refuses these too: with bad reachable from a good commit there is nothing to search
+fn check_good_before_bad(repo: &Repository, good: Oid, bad: Oid) -> Result<(), String> {
+    let before = good != bad
+        && repo
+            .graph_descendant_of(bad, good)
+            .map_err(|e| e.to_string())?;
+    if before {
+        Ok(())
+    } else {
+        Err(format!(
+            "Good commit {} is not an ancestor of bad commit {}; were good and bad swapped?",
+            good, bad
+        ))
+    }
+}
+
+// Picks the next commit to test from candidates given parents first, each with the indices of
+// its parents in range. A commit's weight is how many candidates it can reach, itself
+// included; testing the one closest to half the range discards the most commits either way.
+// Like git, a single-parent commit's weight is its parent's plus one, so only merges need an
+// actual walk, and the search stops at the first commit that splits the range exactly.
+// Also reports whether the culprit is known: nothing is left to test and no skipped commit
+// could be it instead of bad.
+fn select(parents: &[Vec<usize>], skipped: &[bool]) -> (Option<usize>, bool) {
+    let total = parents.len();
+    let mut weights = vec![0usize; total];
+    // Marks which commits the current merge walk has visited, without clearing between walks
+    let mut seen = vec![usize::MAX; total];
+    let mut stack = Vec::new();
+    let mut best: Option<(usize, usize)> = None;
+
+    for i in 0..total {
+        weights[i] = match parents[i].as_slice() {
+            [] => 1,
+            [parent] => weights[*parent] + 1,
+            _ => {
+                let mut count = 0;
+                stack.push(i);
+                seen[i] = i;
+                while let Some(next) = stack.pop() {
+                    count += 1;
+                    for &parent in &parents[next] {
+                        if seen[parent] != i {
+                            seen[parent] = i;
+                            stack.push(parent);
+                        }
+                    }
+                }
+                count
+            }
+        };
+
+        if skipped[i] {
+            continue;
+        }
+        let weight = weights[i];
+        let score = weight.min(total - weight);
+        if score > 0 && best.map(|(s, _)| score > s).unwrap_or(true) {
+            best = Some((score, i));
+            if (2 * weight).abs_diff(total) <= 1 {
+                break;
+            }
+        }
+    }
+
+    let candidate = best.map(|(_, i)| i);
+    let found = candidate.is_none() && total > 0 && !skipped.iter().any(|s| *s);
+    (candidate, found)
+}
+
+fn ref_targets(repo: &Repository, prefix: &str) -> Result<Vec<Oid>, String> {
+    Ok(repo
+        .references_glob(&format!("{}*", prefix))
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .filter_map(|r| r.target())
+        .collect())
+}
+
+fn to_commit(repo: &Repository, oid: Oid) -> BisectCommit {
+    BisectCommit {
+        id: oid.to_string(),
+        summary: repo
+            .find_commit(oid)
+            .ok()
+            .and_then(|c| c.summary().map(|s| s.to_string()))
+            .unwrap_or_default(),
+    }
+}
+
+// A safe checkout refuses to clobber local changes, so a dirty tree surfaces as an error
+fn checkout_detached(repo: &Repository, oid: Oid) -> Result<(), String> {
+    let object = repo.find_object(oid, None).map_err(|e| e.to_string())?;
+    repo.checkout_tree(&object, Some(CheckoutBuilder::new().safe()))
+        .map_err(|e| e.to_string())?;
+    repo.set_head_detached(oid).map_err(|e| e.to_string())
+}
+
+fn write_state_file(repo: &Repository, name: &str, contents: &str) -> Result<(), String> {
+    fs::write(repo.path().join(name), contents).map_err(|e| e.to_string())
+}
+
+fn append_log(repo: &Repository, line: &str) -> Result<(), String> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(repo.path().join(BISECT_LOG))
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::select;
+
+    // Parents for a straight line of `n` commits, oldest first
+    fn linear(n: usize) -> Vec<Vec<usize>> {
+        (0..n)
+            .map(|i| if i == 0 { vec![] } else { vec![i - 1] })
+            .collect()
+    }
+
+    #[test]
+    fn picks_the_middle_of_a_line() {
+        let parents = linear(8);
+        assert_eq!(select(&parents, &[false; 8]), (Some(3), false));
+    }
+
+    #[test]
+    fn steps_around_a_skipped_midpoint() {
+        let parents = linear(8);
+        let mut skipped = [false; 8];
+        skipped[3] = true;
+        let (candidate, found) = select(&parents, &skipped);
+        assert!(matches!(candidate, Some(2) | Some(4)));
+        assert!(!found);
+    }
+
+    #[test]
+    fn reports_bad_once_it_is_the_only_candidate() {
+        assert_eq!(select(&[vec![]], &[false]), (None, true));
+    }
+
+    #[test]
+    fn skipped_commits_leave_the_culprit_open() {
+        // Only bad and a skipped parent remain; either could have introduced the change
+        let parents = linear(2);
+        assert_eq!(select(&parents, &[true, false]), (None, false));
+    }
+
+    #[test]
+    fn nothing_to_search_is_not_a_culprit() {
+        assert_eq!(select(&[], &[]), (None, false));
+    }
+
+    #[test]
+    fn merges_count_each_ancestor_once() {
+        // 0 - 1 - 2 - 3 - 6 (merge of 3 and 5) - 7
+        //      \- 4 - 5 -/
+        let parents = vec![
+            vec![],
+            vec![0],
+            vec![1],
+            vec![2],
+            vec![1],
+            vec![4],
+            vec![3, 5],
+            vec![6],
+        ];
+        // Weights are 1, 2, 3, 4, 3, 4, 7, 8: commits 3 and 5 both split the range evenly
+        assert_eq!(select(&parents, &[false; 8]), (Some(3), false));
+
+        let mut skipped = [false; 8];
+        skipped[3] = true;
+        assert_eq!(select(&parents, &skipped), (Some(5), false));
+    }
+}
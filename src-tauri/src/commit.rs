@@ -0,0 +1,328 @@
+use chrono::DateTime;
+use git2::{message_prettify, Delta, DiffFindOptions, Oid, Patch, Repository, Signature};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use tauri::{command, State};
+
+use crate::app_state::AppStore;
+use crate::diff::status_name;
+use crate::hooks::{self, HookContext, HookResult};
+use crate::lfs::{pointer_for_blob, LfsPointer};
+use crate::message::{self, Trailer, Violation};
+use crate::{format_timestamp, get_refs_by_target, CommitStats, RepositoryState};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Person {
+    name: String,
+    email: String,
+    date: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ParentSummary {
+    id: String,
+    summary: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileChange {
+    path: String,
+    old_path: Option<String>,
+    status: String,
+    binary: bool,
+    insertions: usize,
+    deletions: usize,
+    lfs: Option<LfsPointer>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitDetails {
+    id: String,
+    summary: String,
+    body: String,
+    message: String,
+    author: Person,
+    committer: Person,
+    timestamp: String,
+    parents: Vec<ParentSummary>,
+    refs: Vec<String>,
+    files: Vec<FileChange>,
+    stats: CommitStats,
+    trailers: Vec<Trailer>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitResult {
+    committed: bool,
+    id: Option<String>,
+    hooks: Vec<HookResult>,
+    violations: Vec<Violation>,
+}
+
+// Commits the current index on top of HEAD. With `run_hooks` the pre-commit,
+// prepare-commit-msg, commit-msg and post-commit hooks run as they do for `git commit -m`, and a
+// failing hook aborts the commit. Like `-m`, the message only gets whitespace cleanup unless
+// `strip_comments` is set for text that came from a template or editor with `#` hints.
+// With `validate` the final message is also checked against the saved lint rules, and any
+// violation aborts the commit before anything is written. `trailers` are added to the message
+// before the hooks see it, followed by a Signed-off-by line for the committer with `sign_off`.
+#[command]
+#[allow(clippy::too_many_arguments)]
+pub async fn create_commit(
+    message: String,
+    run_hooks: Option<bool>,
+    validate: Option<bool>,
+    trailers: Option<Vec<Trailer>>,
+    sign_off: Option<bool>,
+    strip_comments: Option<bool>,
+    state: State<'_, RepositoryState>,
+    store: State<'_, AppStore>,
+) -> Result<CommitResult, String> {
+    let run_hooks = run_hooks.unwrap_or(true);
+    let comment_char = if strip_comments.unwrap_or(false) {
+        Some(b'#')
+    } else {
+        None
+    };
+    let mut result = CommitResult {
+        committed: false,
+        id: None,
+        hooks: Vec::new(),
+        violations: Vec::new(),
+    };
+
+    // Hooks run with the repository unlocked so a slow one does not stall every other command
+    let (context, git_dir) = {
+        let repo = state.0.lock().unwrap();
+        let repo = repo.as_ref().ok_or("No repository opened")?;
+        (HookContext::new(repo), repo.path().to_path_buf())
+    };
+
+    if run_hooks {
+        if let Some(hook) = hooks::run(context.clone(), "pre-commit", Vec::new(), None).await? {
+            let success = hook.success;
+            result.hooks.push(hook);
+            if !success {
+                return Ok(result);
+            }
+        }
+    }
+
+    let mut message = message;
+    let mut trailers = trailers.unwrap_or_default();
+    if sign_off.unwrap_or(false) {
+        let (name, email) = with_repo(&state, &git_dir, |repo| {
+            let signature = repo.signature().map_err(|e| e.to_string())?;
+            Ok((
+                signature.name().unwrap_or("").to_string(),
+                signature.email().unwrap_or("").to_string(),
+            ))
+        })?;
+        trailers.push(Trailer {
+            key: "Signed-off-by".to_string(),
+            value: format!("{} <{}>", name, email),
+        });
+    }
+    if !trailers.is_empty() {
+        // Cleaned up first so that trailers follow the message itself, not a template's hints
+        let cleaned = message_prettify(message, comment_char).map_err(|e| e.to_string())?;
+        if cleaned.trim().is_empty() {
+            return Err("Aborting commit due to empty commit message".to_string());
+        }
+        message = message::append_trailers(&cleaned, &trailers)?;
+    }
+
+    if run_hooks {
+        // Both hooks edit the message in place, so it goes through COMMIT_EDITMSG like git does
+        let msg_path = git_dir.join("COMMIT_EDITMSG");
+        fs::write(&msg_path, &message).map_err(|e| e.to_string())?;
+        let path_arg = msg_path.to_string_lossy().to_string();
+
+        let steps = [
+            (
+                "prepare-commit-msg",
+                vec![path_arg.clone(), "message".to_string()],
+            ),
+            ("commit-msg", vec![path_arg]),
+        ];
+        for (name, args) in steps {
+            if let Some(hook) = hooks::run(context.clone(), name, args, None).await? {
+                let success = hook.success;
+                result.hooks.push(hook);
+                if !success {
+                    return Ok(result);
+                }
+                message = fs::read_to_string(&msg_path).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+
+    let message = message_prettify(message, comment_char).map_err(|e| e.to_string())?;
+    if message.trim().is_empty() {
+        return Err("Aborting commit due to empty commit message".to_string());
+    }
+    if validate.unwrap_or(false) {
+        result.violations = message::lint(&message, &store.lint_rules());
+        if !result.violations.is_empty() {
+            return Ok(result);
+        }
+    }
+
+    let oid = with_repo(&state, &git_dir, |repo| {
+        // pre-commit hooks often restage files, so the index is reloaded from disk first
+        let mut index = repo.index().map_err(|e| e.to_string())?;
+        index.read(true).map_err(|e| e.to_string())?;
+        let tree_id = index.write_tree().map_err(|e| e.to_string())?;
+        let tree = repo.find_tree(tree_id).map_err(|e| e.to_string())?;
+
+        let signature = repo.signature().map_err(|e| e.to_string())?;
+        let parent = match repo.head() {
+            Ok(head) => Some(head.peel_to_commit().map_err(|e| e.to_string())?),
+            Err(_) => None,
+        };
+        let parents: Vec<_> = parent.iter().collect();
+
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            &message,
+            &tree,
+            &parents,
+        )
+        .map_err(|e| e.to_string())
+    })?;
+    result.committed = true;
+    result.id = Some(oid.to_string());
+
+    if run_hooks {
+        // post-commit cannot undo anything; its output is only passed along
+        if let Some(hook) = hooks::run(context, "post-commit", Vec::new(), None).await? {
+            result.hooks.push(hook);
+        }
+    }
+
+    Ok(result)
+}
+
+// Re-locks the repository between hooks, refusing to carry on if another one was opened
+fn with_repo<T>(
+    state: &RepositoryState,
+    git_dir: &Path,
+    f: impl FnOnce(&Repository) -> Result<T, String>,
+) -> Result<T, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+    if repo.path() != git_dir {
+        return Err("The repository changed while committing".to_string());
+    }
+    f(repo)
+}
+
+#[command]
+pub async fn get_commit_details(
+    oid: String,
+    state: State<'_, RepositoryState>,
+) -> Result<CommitDetails, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let oid = Oid::from_str(&oid).map_err(|e| e.to_string())?;
+    let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+
+    // Merges are shown against their first parent, the same view `git show --first-parent` gives
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(|e| e.to_string())?),
+        Err(_) => None,
+    };
+    let tree = commit.tree().map_err(|e| e.to_string())?;
+    let mut diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+        .map_err(|e| e.to_string())?;
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))
+        .map_err(|e| e.to_string())?;
+
+    let mut files = Vec::new();
+    for index in 0..diff.deltas().len() {
+        let patch = match Patch::from_diff(&diff, index).map_err(|e| e.to_string())? {
+            Some(patch) => patch,
+            None => continue,
+        };
+        let delta = patch.delta();
+        let (_, insertions, deletions) = patch.line_stats().map_err(|e| e.to_string())?;
+
+        let old_path = delta
+            .old_file()
+            .path()
+            .map(|p| p.to_string_lossy().to_string());
+        let path = delta
+            .new_file()
+            .path()
+            .map(|p| p.to_string_lossy().to_string())
+            .or_else(|| old_path.clone())
+            .unwrap_or_default();
+
+        // Deleted files only have an old side to inspect
+        let lfs_blob = if delta.status() == Delta::Deleted {
+            delta.old_file().id()
+        } else {
+            delta.new_file().id()
+        };
+
+        files.push(FileChange {
+            old_path: old_path.filter(|old| *old != path),
+            path,
+            status: status_name(delta.status()).to_string(),
+            binary: delta.flags().is_binary(),
+            insertions,
+            deletions,
+            lfs: pointer_for_blob(repo, lfs_blob),
+        });
+    }
+
+    let stats = CommitStats {
+        files_changed: files.len(),
+        insertions: files.iter().map(|f| f.insertions).sum(),
+        deletions: files.iter().map(|f| f.deletions).sum(),
+    };
+
+    let parents = commit
+        .parents()
+        .map(|parent| ParentSummary {
+            id: parent.id().to_string(),
+            summary: parent.summary().unwrap_or("").to_string(),
+        })
+        .collect();
+
+    let refs = get_refs_by_target(repo)?.remove(&oid).unwrap_or_default();
+
+    let details = CommitDetails {
+        id: oid.to_string(),
+        summary: commit.summary().unwrap_or("").to_string(),
+        // Trailers are split out so co-authors and sign-offs can be shown apart from the body
+        body: message::strip_trailers(commit.body().unwrap_or("")).to_string(),
+        message: commit.message().unwrap_or("").to_string(),
+        author: to_person(&commit.author()),
+        committer: to_person(&commit.committer()),
+        timestamp: format_timestamp(commit.time()),
+        parents,
+        refs,
+        files,
+        stats,
+        trailers: message::parse_trailers(commit.message().unwrap_or("")),
+    };
+    Ok(details)
+}
+
+fn to_person(signature: &Signature) -> Person {
+    let date = DateTime::from_timestamp(signature.when().seconds(), 0)
+        .map(|dt| dt.to_rfc3339())
+        .unwrap_or_default();
+
+    Person {
+        name: signature.name().unwrap_or("").to_string(),
+        email: signature.email().unwrap_or("").to_string(),
+        date,
+    }
+}
@@ -0,0 +1,116 @@
+use git2::{Oid, Repository, Sort};
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+use crate::{format_timestamp, resolve_commit, CommitStats, RepositoryState};
+
+// Commit lists are capped; the ahead/behind counts are always exact
+const DEFAULT_COMMIT_LIMIT: usize = 250;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CommitSummary {
+    id: String,
+    summary: String,
+    author: String,
+    timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RefComparison {
+    base: String,
+    head: String,
+    base_id: String,
+    head_id: String,
+    merge_base: Option<String>,
+    ahead: usize,
+    behind: usize,
+    ahead_commits: Vec<CommitSummary>,
+    behind_commits: Vec<CommitSummary>,
+    stats: CommitStats,
+}
+
+// Compares `head` against `base` the way a pull request does: commits unique to each side,
+// plus the diffstat of `head` against the point where the two diverged
+#[command]
+pub async fn compare_refs(
+    base: String,
+    head: String,
+    limit: Option<usize>,
+    state: State<'_, RepositoryState>,
+) -> Result<RefComparison, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let limit = limit.unwrap_or(DEFAULT_COMMIT_LIMIT);
+    let base_id = resolve_commit(repo, &base)?;
+    let head_id = resolve_commit(repo, &head)?;
+
+    let (ahead, behind) = repo
+        .graph_ahead_behind(head_id, base_id)
+        .map_err(|e| e.to_string())?;
+    let merge_base = repo.merge_base(base_id, head_id).ok();
+
+    let ahead_commits = unique_commits(repo, head_id, base_id, limit)?;
+    let behind_commits = unique_commits(repo, base_id, head_id, limit)?;
+
+    // Unrelated histories have no merge base, so fall back to a straight tree diff
+    let from = merge_base.unwrap_or(base_id);
+    let from_tree = repo
+        .find_commit(from)
+        .and_then(|c| c.tree())
+        .map_err(|e| e.to_string())?;
+    let head_tree = repo
+        .find_commit(head_id)
+        .and_then(|c| c.tree())
+        .map_err(|e| e.to_string())?;
+    let diff_stats = repo
+        .diff_tree_to_tree(Some(&from_tree), Some(&head_tree), None)
+        .and_then(|diff| diff.stats())
+        .map_err(|e| e.to_string())?;
+
+    Ok(RefComparison {
+        base,
+        head,
+        base_id: base_id.to_string(),
+        head_id: head_id.to_string(),
+        merge_base: merge_base.map(|oid| oid.to_string()),
+        ahead,
+        behind,
+        ahead_commits,
+        behind_commits,
+        stats: CommitStats {
+            files_changed: diff_stats.files_changed(),
+            insertions: diff_stats.insertions(),
+            deletions: diff_stats.deletions(),
+        },
+    })
+}
+
+// Commits reachable from `from` but not from `hidden`, newest first
+fn unique_commits(
+    repo: &Repository,
+    from: Oid,
+    hidden: Oid,
+    limit: usize,
+) -> Result<Vec<CommitSummary>, String> {
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    revwalk
+        .set_sorting(Sort::TOPOLOGICAL | Sort::TIME)
+        .map_err(|e| e.to_string())?;
+    revwalk.push(from).map_err(|e| e.to_string())?;
+    revwalk.hide(hidden).map_err(|e| e.to_string())?;
+
+    let mut commits = Vec::new();
+    for oid in revwalk.take(limit) {
+        let oid = oid.map_err(|e| e.to_string())?;
+        let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+        commits.push(CommitSummary {
+            id: oid.to_string(),
+            summary: commit.summary().unwrap_or("").to_string(),
+            author: commit.author().name().unwrap_or("").to_string(),
+            timestamp: format_timestamp(commit.time()),
+        });
+    }
+
+    Ok(commits)
+}
@@ -0,0 +1,468 @@
+use git2::{Delta, Diff, DiffFindOptions, DiffOptions, Oid, Patch, Repository};
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+
+use crate::lfs::{pointer_for_blob, LfsPointer};
+use crate::RepositoryState;
+
+// Word diffs are quadratic in the number of tokens, so very long lines are shown whole
+const MAX_INTRALINE_TOKENS: usize = 400;
+// How many added lines a removed line is compared against before it is left unpaired
+const PAIRING_WINDOW: usize = 3;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiffSettings {
+    ignore_whitespace: bool,
+    ignore_whitespace_change: bool,
+    ignore_whitespace_eol: bool,
+    ignore_blank_lines: bool,
+    context_lines: Option<u32>,
+    // Word-level highlighting of modified lines; on unless turned off
+    intraline: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Segment {
+    text: String,
+    changed: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffLine {
+    // "context", "addition", "deletion", or "eof" for the missing-newline marker
+    origin: String,
+    old_lineno: Option<u32>,
+    new_lineno: Option<u32>,
+    content: String,
+    segments: Option<Vec<Segment>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DiffHunk {
+    header: String,
+    old_start: u32,
+    old_lines: u32,
+    new_start: u32,
+    new_lines: u32,
+    lines: Vec<DiffLine>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileDiff {
+    path: String,
+    old_path: Option<String>,
+    status: String,
+    binary: bool,
+    insertions: usize,
+    deletions: usize,
+    lfs: Option<LfsPointer>,
+    hunks: Vec<DiffHunk>,
+}
+
+// The diff a commit introduces, against its first parent like `get_commit_details`
+#[command]
+pub async fn get_commit_diff(
+    oid: String,
+    path: Option<String>,
+    options: Option<DiffSettings>,
+    state: State<'_, RepositoryState>,
+) -> Result<Vec<FileDiff>, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let settings = options.unwrap_or_default();
+    let oid = Oid::from_str(&oid).map_err(|e| e.to_string())?;
+    let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree().map_err(|e| e.to_string())?),
+        Err(_) => None,
+    };
+    let tree = commit.tree().map_err(|e| e.to_string())?;
+
+    let mut opts = diff_options(&settings, path.as_deref());
+    let diff = repo
+        .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), Some(&mut opts))
+        .map_err(|e| e.to_string())?;
+    collect(repo, diff, &settings)
+}
+
+// Unstaged changes by default; `staged` shows what the next commit would contain instead
+#[command]
+pub async fn get_working_diff(
+    staged: Option<bool>,
+    path: Option<String>,
+    options: Option<DiffSettings>,
+    state: State<'_, RepositoryState>,
+) -> Result<Vec<FileDiff>, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let settings = options.unwrap_or_default();
+    let mut opts = diff_options(&settings, path.as_deref());
+    let diff = if staged.unwrap_or(false) {
+        // An unborn branch has no HEAD tree, so everything staged shows up as new
+        let head_tree = repo.head().and_then(|head| head.peel_to_tree()).ok();
+        repo.diff_tree_to_index(head_tree.as_ref(), None, Some(&mut opts))
+    } else {
+        opts.include_untracked(true)
+            .recurse_untracked_dirs(true)
+            .show_untracked_content(true);
+        repo.diff_index_to_workdir(None, Some(&mut opts))
+    }
+    .map_err(|e| e.to_string())?;
+    collect(repo, diff, &settings)
+}
+
+pub fn status_name(status: Delta) -> &'static str {
+    match status {
+        Delta::Added | Delta::Untracked => "new",
+        Delta::Deleted => "deleted",
+        Delta::Modified => "modified",
+        Delta::Renamed => "renamed",
+        Delta::Copied => "copied",
+        Delta::Typechange => "typechange",
+        _ => "unknown",
+    }
+}
+
+fn diff_options(settings: &DiffSettings, path: Option<&str>) -> DiffOptions {
+    let mut opts = DiffOptions::new();
+    opts.ignore_whitespace(settings.ignore_whitespace)
+        .ignore_whitespace_change(settings.ignore_whitespace_change)
+        .ignore_whitespace_eol(settings.ignore_whitespace_eol)
+        .ignore_blank_lines(settings.ignore_blank_lines)
+        .context_lines(settings.context_lines.unwrap_or(3));
+    if let Some(path) = path {
+        opts.pathspec(path).disable_pathspec_match(true);
+    }
+    opts
+}
+
+fn collect(
+    repo: &Repository,
+    mut diff: Diff,
+    settings: &DiffSettings,
+) -> Result<Vec<FileDiff>, String> {
+    diff.find_similar(Some(DiffFindOptions::new().renames(true)))
+        .map_err(|e| e.to_string())?;
+
+    let intraline = settings.intraline.unwrap_or(true);
+    // Whitespace that the diff was told to ignore should not light up inside a line either
+    let ignore_spaces = settings.ignore_whitespace || settings.ignore_whitespace_change;
+
+    let mut files = Vec::new();
+    for index in 0..diff.deltas().len() {
+        let patch = match Patch::from_diff(&diff, index).map_err(|e| e.to_string())? {
+            Some(patch) => patch,
+            None => continue,
+        };
+        let delta = patch.delta();
+        let (_, insertions, deletions) = patch.line_stats().map_err(|e| e.to_string())?;
+
+        let old_path = delta
+            .old_file()
+            .path()
+            .map(|p| p.to_string_lossy().to_string());
+        let path = delta
+            .new_file()
+            .path()
+            .map(|p| p.to_string_lossy().to_string())
+            .or_else(|| old_path.clone())
+            .unwrap_or_default();
+
+        // With whitespace ignored a file can end up with no hunks at all; git diff -w skips those too
+        let mode_changed = delta.old_file().mode() != delta.new_file().mode();
+        if patch.num_hunks() == 0
+            && delta.status() == Delta::Modified
+            && !delta.flags().is_binary()
+            && !mode_changed
+        {
+            continue;
+        }
+
+        // The pointer text says nothing useful, so LFS files are described instead of diffed
+        let (primary, fallback) = if delta.status() == Delta::Deleted {
+            (delta.old_file().id(), delta.new_file().id())
+        } else {
+            (delta.new_file().id(), delta.old_file().id())
+        };
+        let lfs = pointer_for_blob(repo, primary).or_else(|| pointer_for_blob(repo, fallback));
+        let hunk_count = if lfs.is_some() { 0 } else { patch.num_hunks() };
+
+        let mut hunks = Vec::new();
+        for h in 0..hunk_count {
+            let (hunk, line_count) = patch.hunk(h).map_err(|e| e.to_string())?;
+            let mut lines = Vec::with_capacity(line_count);
+            for l in 0..line_count {
+                let line = patch.line_in_hunk(h, l).map_err(|e| e.to_string())?;
+                let origin = match line.origin() {
+                    '+' => "addition",
+                    '-' => "deletion",
+                    '=' | '>' | '<' => "eof",
+                    _ => "context",
+                };
+                let content = String::from_utf8_lossy(line.content());
+                lines.push(DiffLine {
+                    origin: origin.to_string(),
+                    old_lineno: line.old_lineno(),
+                    new_lineno: line.new_lineno(),
+                    content: content.trim_matches(['\n', '\r']).to_string(),
+                    segments: None,
+                });
+            }
+            if intraline {
+                highlight_changes(&mut lines, ignore_spaces);
+            }
+
+            hunks.push(DiffHunk {
+                header: String::from_utf8_lossy(hunk.header())
+                    .trim_end()
+                    .to_string(),
+                old_start: hunk.old_start(),
+                old_lines: hunk.old_lines(),
+                new_start: hunk.new_start(),
+                new_lines: hunk.new_lines(),
+                lines,
+            });
+        }
+
+        files.push(FileDiff {
+            old_path: old_path.filter(|old| *old != path),
+            path,
+            status: status_name(delta.status()).to_string(),
+            binary: delta.flags().is_binary(),
+            insertions,
+            deletions,
+            lfs,
+            hunks,
+        });
+    }
+
+    Ok(files)
+}
+
+// A run of deletions directly followed by a run of additions is treated as lines being
+// modified in place. Each removed line is paired, in order, with one of the next few added
+// lines it has words in common with, so an inserted blank line does not throw the pairing off.
+fn highlight_changes(lines: &mut [DiffLine], ignore_spaces: bool) {
+    let mut i = 0;
+    while i < lines.len() {
+        if lines[i].origin != "deletion" {
+            i += 1;
+            continue;
+        }
+        let del_start = i;
+        while i < lines.len() && lines[i].origin == "deletion" {
+            i += 1;
+        }
+        let add_start = i;
+        while i < lines.len() && lines[i].origin == "addition" {
+            i += 1;
+        }
+
+        let mut next_add = add_start;
+        for old in del_start..add_start {
+            for new in next_add..i.min(next_add + PAIRING_WINDOW) {
+                if let Some((old_segments, new_segments)) =
+                    word_diff(&lines[old].content, &lines[new].content, ignore_spaces)
+                {
+                    lines[old].segments = Some(old_segments);
+                    lines[new].segments = Some(new_segments);
+                    next_add = new + 1;
+                    break;
+                }
+            }
+        }
+    }
+}
+
+// Returns `None` when the lines have nothing meaningful in common, since highlighting
+// every word of a rewritten line says no more than the line colour already does
+fn word_diff(old: &str, new: &str, ignore_spaces: bool) -> Option<(Vec<Segment>, Vec<Segment>)> {
+    let old_tokens = tokenize(old);
+    let new_tokens = tokenize(new);
+    if old_tokens.len() > MAX_INTRALINE_TOKENS || new_tokens.len() > MAX_INTRALINE_TOKENS {
+        return None;
+    }
+
+    let same =
+        |a: &str, b: &str| a == b || (ignore_spaces && a.trim().is_empty() && b.trim().is_empty());
+
+    // Longest common subsequence over tokens, filled from the end so it can be read forwards
+    let (n, m) = (old_tokens.len(), new_tokens.len());
+    let mut table = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if same(old_tokens[i], new_tokens[j]) {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let mut old_changed = vec![true; n];
+    let mut new_changed = vec![true; m];
+    let (mut i, mut j) = (0, 0);
+    let mut common_words = false;
+    while i < n && j < m {
+        if same(old_tokens[i], new_tokens[j]) {
+            old_changed[i] = false;
+            new_changed[j] = false;
+            // Shared punctuation or spacing alone does not make two lines related
+            common_words |= old_tokens[i].starts_with(is_word);
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+
+    if !common_words {
+        return None;
+    }
+    Some((
+        segments(&old_tokens, &old_changed),
+        segments(&new_tokens, &new_changed),
+    ))
+}
+
+// Words, runs of whitespace, and single punctuation characters
+fn tokenize(line: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut chars = line.char_indices().peekable();
+    while let Some((_, c)) = chars.next() {
+        let end = match chars.peek() {
+            Some(&(next_index, next)) => {
+                let same_class =
+                    (is_word(c) && is_word(next)) || (c.is_whitespace() && next.is_whitespace());
+                if same_class {
+                    continue;
+                }
+                next_index
+            }
+            None => line.len(),
+        };
+        tokens.push(&line[start..end]);
+        start = end;
+    }
+    tokens
+}
+
+fn is_word(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Neighbouring tokens with the same state are merged so the frontend gets as few spans as possible
+fn segments(tokens: &[&str], changed: &[bool]) -> Vec<Segment> {
+    let mut segments: Vec<Segment> = Vec::new();
+    for (token, &changed) in tokens.iter().zip(changed) {
+        match segments.last_mut() {
+            Some(last) if last.changed == changed => last.text.push_str(token),
+            _ => segments.push(Segment {
+                text: token.to_string(),
+                changed,
+            }),
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(origin: &str, content: &str) -> DiffLine {
+        DiffLine {
+            origin: origin.to_string(),
+            old_lineno: None,
+            new_lineno: None,
+            content: content.to_string(),
+            segments: None,
+        }
+    }
+
+    // Renders segments with changed text in brackets, e.g. `let x = [2];`
+    fn render(segments: &[Segment]) -> String {
+        segments
+            .iter()
+            .map(|s| {
+                if s.changed {
+                    format!("[{}]", s.text)
+                } else {
+                    s.text.clone()
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tokenizes_words_spaces_and_punctuation() {
+        assert_eq!(
+            tokenize("let  snake_case=f(1);"),
+            vec!["let", "  ", "snake_case", "=", "f", "(", "1", ")", ";"]
+        );
+        assert_eq!(tokenize("héllo wörld"), vec!["héllo", " ", "wörld"]);
+        assert!(tokenize("").is_empty());
+    }
+
+    #[test]
+    fn marks_only_the_changed_words() {
+        let (old, new) = word_diff("    call(a, b);", "    call(a, c);", false).unwrap();
+        assert_eq!(render(&old), "    call(a, [b]);");
+        assert_eq!(render(&new), "    call(a, [c]);");
+    }
+
+    #[test]
+    fn ignored_whitespace_is_not_highlighted() {
+        let (_, new) = word_diff("let x = 1;", "let  x = 2;", true).unwrap();
+        assert_eq!(render(&new), "let  x = [2];");
+
+        let (_, new) = word_diff("let x = 1;", "let  x = 2;", false).unwrap();
+        assert_eq!(render(&new), "let[  ]x = [2];");
+    }
+
+    #[test]
+    fn unrelated_lines_are_left_whole() {
+        // Only punctuation and spaces in common
+        assert!(word_diff("foo();", "bar();", false).is_none());
+        assert!(word_diff("}", "}", false).is_none());
+    }
+
+    #[test]
+    fn pairs_lines_across_an_inserted_blank_line() {
+        let mut lines = vec![
+            line("context", "fn main() {"),
+            line("deletion", "    let x = 1;"),
+            line("addition", ""),
+            line("addition", "    let x = 2;"),
+            line("context", "}"),
+        ];
+        highlight_changes(&mut lines, false);
+
+        assert_eq!(
+            render(lines[1].segments.as_ref().unwrap()),
+            "    let x = [1];"
+        );
+        assert!(lines[2].segments.is_none());
+        assert_eq!(
+            render(lines[3].segments.as_ref().unwrap()),
+            "    let x = [2];"
+        );
+    }
+
+    #[test]
+    fn pairing_looks_only_a_few_lines_ahead() {
+        let mut lines = vec![line("deletion", "value = old")];
+        for i in 0..PAIRING_WINDOW {
+            lines.push(line("addition", &format!("other{}", i)));
+        }
+        lines.push(line("addition", "value = new"));
+        highlight_changes(&mut lines, false);
+        assert!(lines.iter().all(|l| l.segments.is_none()));
+    }
+}
@@ -0,0 +1,243 @@
+use git2::Oid;
+use serde::{Deserialize, Serialize};
+
+use crate::get_commit_color;
+
+// Line segment drawn between a commit row and the row below it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphEdge {
+    from_lane: usize,
+    to_lane: usize,
+    color: String,
+    #[serde(rename = "type")]
+    edge_type: String,
+    parent: Option<String>,
+}
+
+// Lane and edge data for a single commit in the history list
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GraphRow {
+    pub lane: usize,
+    pub color: String,
+    pub lane_count: usize,
+    edges: Vec<GraphEdge>,
+}
+
+// An active lane waiting for the commit it points at to show up
+struct Lane {
+    target: Oid,
+    color: usize,
+}
+
+// Assigns lanes to commits fed in topological order (children before parents).
+// Each lane tracks the next commit it expects, so a parent reached from several
+// children shares a single lane and freed lanes are reused left to right.
+#[derive(Default)]
+pub struct GraphBuilder {
+    lanes: Vec<Option<Lane>>,
+    next_color: usize,
+}
+
+impl GraphBuilder {
+    pub fn next_row(&mut self, oid: Oid, parents: &[Oid]) -> GraphRow {
+        // Continue the lane that was waiting for this commit, or open a new one for a tip
+        let (lane, color) = match self.find_lane(oid) {
+            Some(index) => {
+                let color = self.lanes[index].as_ref().map(|l| l.color).unwrap_or(0);
+                (index, color)
+            }
+            None => {
+                let color = self.take_color();
+                (self.free_lane(), color)
+            }
+        };
+        self.lanes[lane] = None;
+
+        // Every other active lane passes straight through this row
+        let mut edges: Vec<GraphEdge> = self
+            .lanes
+            .iter()
+            .enumerate()
+            .filter_map(|(index, l)| {
+                l.as_ref().map(|l| GraphEdge {
+                    from_lane: index,
+                    to_lane: index,
+                    color: get_commit_color(l.color),
+                    edge_type: "pass".to_string(),
+                    parent: None,
+                })
+            })
+            .collect();
+
+        for (i, parent) in parents.iter().enumerate() {
+            let edge_type = if i == 0 { "parent" } else { "merge" };
+
+            let (to_lane, edge_color) = match self.find_lane(*parent) {
+                // Parent already has a lane, so route into it instead of duplicating it
+                Some(index) => {
+                    let target_color = self.lanes[index].as_ref().map(|l| l.color).unwrap_or(0);
+                    (index, if i == 0 { color } else { target_color })
+                }
+                None if i == 0 => {
+                    self.lanes[lane] = Some(Lane {
+                        target: *parent,
+                        color,
+                    });
+                    (lane, color)
+                }
+                None => {
+                    let merge_color = self.take_color();
+                    let index = self.free_lane();
+                    self.lanes[index] = Some(Lane {
+                        target: *parent,
+                        color: merge_color,
+                    });
+                    (index, merge_color)
+                }
+            };
+
+            edges.push(GraphEdge {
+                from_lane: lane,
+                to_lane,
+                color: get_commit_color(edge_color),
+                edge_type: edge_type.to_string(),
+                parent: Some(parent.to_string()),
+            });
+        }
+
+        let lane_count = self.lanes.len().max(lane + 1);
+        while matches!(self.lanes.last(), Some(None)) {
+            self.lanes.pop();
+        }
+
+        GraphRow {
+            lane,
+            color: get_commit_color(color),
+            lane_count,
+            edges,
+        }
+    }
+
+    fn find_lane(&self, oid: Oid) -> Option<usize> {
+        self.lanes
+            .iter()
+            .position(|l| l.as_ref().map(|l| l.target == oid).unwrap_or(false))
+    }
+
+    fn free_lane(&mut self) -> usize {
+        match self.lanes.iter().position(|l| l.is_none()) {
+            Some(index) => index,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        }
+    }
+
+    fn take_color(&mut self) -> usize {
+        let color = self.next_color;
+        self.next_color += 1;
+        color
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oid(n: u8) -> Oid {
+        Oid::from_bytes(&[n; 20]).unwrap()
+    }
+
+    // (commit, parents) pairs in the order the history list walks them
+    fn layout(commits: &[(u8, &[u8])]) -> Vec<GraphRow> {
+        let mut graph = GraphBuilder::default();
+        commits
+            .iter()
+            .map(|(commit, parents)| {
+                let parents: Vec<Oid> = parents.iter().map(|p| oid(*p)).collect();
+                graph.next_row(oid(*commit), &parents)
+            })
+            .collect()
+    }
+
+    fn edges(row: &GraphRow) -> Vec<(usize, usize, &str)> {
+        row.edges
+            .iter()
+            .map(|e| (e.from_lane, e.to_lane, e.edge_type.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn linear_history_stays_in_one_lane() {
+        let rows = layout(&[(3, &[2]), (2, &[1]), (1, &[])]);
+        assert!(rows.iter().all(|r| r.lane == 0 && r.lane_count == 1));
+        assert_eq!(edges(&rows[0]), vec![(0, 0, "parent")]);
+        assert!(rows[2].edges.is_empty());
+    }
+
+    #[test]
+    fn merge_opens_a_lane_that_joins_the_shared_parent() {
+        // 4 merges 3 into 2, both of which branch off 1
+        let rows = layout(&[(4, &[2, 3]), (2, &[1]), (3, &[1]), (1, &[])]);
+
+        assert_eq!(edges(&rows[0]), vec![(0, 0, "parent"), (0, 1, "merge")]);
+        assert_ne!(rows[0].edges[0].color, rows[0].edges[1].color);
+        assert_eq!(rows[0].lane_count, 2);
+
+        assert_eq!(rows[1].lane, 0);
+        assert_eq!(edges(&rows[1]), vec![(1, 1, "pass"), (0, 0, "parent")]);
+
+        // The side branch routes back into the lane already waiting for 1
+        assert_eq!(rows[2].lane, 1);
+        assert_eq!(rows[2].color, rows[0].edges[1].color);
+        assert_eq!(edges(&rows[2]), vec![(0, 0, "pass"), (1, 0, "parent")]);
+
+        assert_eq!(rows[3].lane, 0);
+        assert_eq!(rows[3].lane_count, 1);
+    }
+
+    #[test]
+    fn freed_lanes_are_reused_from_the_left() {
+        // Three tips side by side; the middle one is a root, so its lane frees up
+        let rows = layout(&[
+            (10, &[1]),
+            (20, &[]),
+            (30, &[3]),
+            (40, &[4]),
+            (1, &[]),
+            (3, &[]),
+            (4, &[]),
+        ]);
+
+        assert_eq!(
+            rows.iter().map(|r| r.lane).collect::<Vec<_>>(),
+            vec![0, 1, 1, 2, 0, 1, 2]
+        );
+        assert_eq!(rows[3].lane_count, 3);
+        assert_eq!(rows[4].lane_count, 3);
+    }
+
+    #[test]
+    fn same_history_gives_the_same_layout() {
+        let history: &[(u8, &[u8])] = &[
+            (6, &[5, 4]),
+            (5, &[3]),
+            (4, &[3, 2]),
+            (3, &[1]),
+            (2, &[1]),
+            (1, &[]),
+        ];
+        let first = serde_json::to_string(&layout(history)).unwrap();
+        let second = serde_json::to_string(&layout(history)).unwrap();
+        assert_eq!(first, second);
+
+        // Loading fewer commits does not change the rows that are shown
+        let partial = serde_json::to_value(layout(&history[..3])).unwrap();
+        let full = serde_json::to_value(layout(history)).unwrap();
+        assert_eq!(
+            partial.as_array().unwrap()[..],
+            full.as_array().unwrap()[..3]
+        );
+    }
+}
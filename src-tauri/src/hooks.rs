@@ -0,0 +1,211 @@
+use git2::Repository;
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use tauri::{command, State};
+
+use crate::RepositoryState;
+
+const KNOWN_HOOKS: &[&str] = &[
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "pre-receive",
+    "update",
+    "post-receive",
+    "post-update",
+    "push-to-checkout",
+    "pre-auto-gc",
+    "post-rewrite",
+];
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HookInfo {
+    name: String,
+    path: String,
+    executable: bool,
+    sample: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HookResult {
+    pub hook: String,
+    pub success: bool,
+    exit_code: Option<i32>,
+    stdout: String,
+    stderr: String,
+}
+
+#[command]
+pub async fn list_hooks(state: State<'_, RepositoryState>) -> Result<Vec<HookInfo>, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let dir = hooks_dir(repo);
+    let mut hooks = Vec::new();
+    for name in KNOWN_HOOKS {
+        let path = dir.join(name);
+        let sample = dir.join(format!("{}.sample", name));
+
+        // An installed hook shadows its sample, so only one entry is listed per name
+        let (path, is_sample) = if path.is_file() {
+            (path, false)
+        } else if sample.is_file() {
+            (sample, true)
+        } else {
+            continue;
+        };
+
+        hooks.push(HookInfo {
+            name: name.to_string(),
+            executable: !is_sample && is_executable(&path),
+            path: path.to_string_lossy().to_string(),
+            sample: is_sample,
+        });
+    }
+
+    Ok(hooks)
+}
+
+#[command]
+pub async fn run_hook(
+    name: String,
+    args: Option<Vec<String>>,
+    stdin: Option<String>,
+    state: State<'_, RepositoryState>,
+) -> Result<Option<HookResult>, String> {
+    if !KNOWN_HOOKS.contains(&name.as_str()) {
+        return Err(format!("Unknown hook: {}", name));
+    }
+
+    let context = {
+        let repo = state.0.lock().unwrap();
+        let repo = repo.as_ref().ok_or("No repository opened")?;
+        HookContext::new(repo)
+    };
+    run(context, &name, args.unwrap_or_default(), stdin).await
+}
+
+// Hooks live in `core.hooksPath` when it is set, otherwise in `$GIT_DIR/hooks`
+pub fn hooks_dir(repo: &Repository) -> PathBuf {
+    let configured = repo
+        .config()
+        .and_then(|config| config.get_path("core.hooksPath"))
+        .ok();
+
+    match configured {
+        Some(path) if path.is_absolute() => path,
+        // Relative paths are resolved from the top of the work tree, as git does
+        Some(path) => repo.workdir().unwrap_or_else(|| repo.path()).join(path),
+        None => repo.path().join("hooks"),
+    }
+}
+
+// Everything a hook needs from the repository, so the repository lock can be released
+// while the hook runs
+#[derive(Debug, Clone)]
+pub struct HookContext {
+    dir: PathBuf,
+    git_dir: PathBuf,
+    workdir: PathBuf,
+}
+
+impl HookContext {
+    pub fn new(repo: &Repository) -> Self {
+        HookContext {
+            dir: hooks_dir(repo),
+            git_dir: repo.path().to_path_buf(),
+            workdir: repo.workdir().unwrap_or_else(|| repo.path()).to_path_buf(),
+        }
+    }
+}
+
+// Runs a hook the way git would, returning `None` when it is not installed or not executable.
+// Hooks such as a test-suite pre-commit can take minutes, so they run on the blocking pool.
+pub async fn run(
+    context: HookContext,
+    name: &str,
+    args: Vec<String>,
+    stdin: Option<String>,
+) -> Result<Option<HookResult>, String> {
+    let name = name.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        run_blocking(&context, &name, &args, stdin.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+fn run_blocking(
+    context: &HookContext,
+    name: &str,
+    args: &[String],
+    stdin: Option<&str>,
+) -> Result<Option<HookResult>, String> {
+    let path = context.dir.join(name);
+    if !path.is_file() || !is_executable(&path) {
+        return Ok(None);
+    }
+
+    // Windows cannot execute hook scripts directly; Git for Windows hands them to its `sh`
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("sh");
+        cmd.arg(&path);
+        cmd
+    } else {
+        Command::new(&path)
+    };
+
+    let mut child = cmd
+        .args(args)
+        .current_dir(&context.workdir)
+        .env("GIT_DIR", &context.git_dir)
+        .env("GIT_INDEX_FILE", context.git_dir.join("index"))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run {} hook: {}", name, e))?;
+
+    if let Some(mut pipe) = child.stdin.take() {
+        // A hook that exits without reading its input closes the pipe early; that is not an error
+        let _ = pipe.write_all(stdin.unwrap_or("").as_bytes());
+    }
+
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to run {} hook: {}", name, e))?;
+
+    Ok(Some(HookResult {
+        hook: name.to_string(),
+        success: output.status.success(),
+        exit_code: output.status.code(),
+        stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+        stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+    }))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::metadata(path)
+        .map(|metadata| metadata.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
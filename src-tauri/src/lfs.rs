@@ -0,0 +1,349 @@
+use git2::{ObjectType, Oid, Repository, TreeWalkMode, TreeWalkResult};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use tauri::{command, State, Window};
+
+use crate::RepositoryState;
+
+const POINTER_VERSION: &str = "version https://git-lfs.github.com/spec/v1";
+// The spec caps pointer files well below this, so larger blobs never need to be read
+const MAX_POINTER_SIZE: usize = 1024;
+// Keeps progress files apart when a pull and a fetch run at the same time
+static NEXT_PROGRESS_FILE: AtomicUsize = AtomicUsize::new(0);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LfsPointer {
+    oid: String,
+    size: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LfsFileStatus {
+    path: String,
+    oid: String,
+    size: u64,
+    downloaded: bool,
+    checked_out: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LfsStatus {
+    enabled: bool,
+    files: Vec<LfsFileStatus>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LfsProgress {
+    operation: String,
+    direction: String,
+    current_file: u64,
+    total_files: u64,
+    bytes_done: u64,
+    bytes_total: u64,
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LfsCommandResult {
+    success: bool,
+    output: String,
+}
+
+pub fn parse_pointer(data: &[u8]) -> Option<LfsPointer> {
+    if data.len() > MAX_POINTER_SIZE {
+        return None;
+    }
+    let text = std::str::from_utf8(data).ok()?;
+    let mut lines = text.lines();
+    if lines.next()? != POINTER_VERSION {
+        return None;
+    }
+
+    let (mut oid, mut size) = (None, None);
+    for line in lines {
+        if let Some(value) = line.strip_prefix("oid sha256:") {
+            oid = Some(value.to_string());
+        } else if let Some(value) = line.strip_prefix("size ") {
+            size = value.parse().ok();
+        }
+    }
+
+    Some(LfsPointer {
+        oid: oid.filter(|o| o.len() == 64 && o.chars().all(|c| c.is_ascii_hexdigit()))?,
+        size: size?,
+    })
+}
+
+// Checks the object header first so that only pointer-sized blobs get inflated
+pub fn pointer_for_blob(repo: &Repository, oid: Oid) -> Option<LfsPointer> {
+    let (size, kind) = repo.odb().ok()?.read_header(oid).ok()?;
+    if kind != ObjectType::Blob || size > MAX_POINTER_SIZE {
+        return None;
+    }
+    parse_pointer(repo.find_blob(oid).ok()?.content())
+}
+
+#[command]
+pub async fn lfs_status(state: State<'_, RepositoryState>) -> Result<LfsStatus, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let tree = match repo.head().and_then(|head| head.peel_to_tree()) {
+        Ok(tree) => tree,
+        Err(_) => {
+            return Ok(LfsStatus {
+                enabled: false,
+                files: Vec::new(),
+            })
+        }
+    };
+
+    let mut pointers = Vec::new();
+    tree.walk(TreeWalkMode::PreOrder, |dir, entry| {
+        if entry.kind() == Some(ObjectType::Blob) {
+            if let Some(pointer) = pointer_for_blob(repo, entry.id()) {
+                pointers.push((format!("{}{}", dir, entry.name().unwrap_or("")), pointer));
+            }
+        }
+        TreeWalkResult::Ok
+    })
+    .map_err(|e| e.to_string())?;
+
+    let workdir = repo.workdir();
+    let files = pointers
+        .into_iter()
+        .map(|(path, pointer)| {
+            // A file that still holds pointer text in the work tree was never smudged
+            let checked_out = workdir
+                .map(|dir| is_smudged(&dir.join(&path)))
+                .unwrap_or(false);
+
+            LfsFileStatus {
+                downloaded: object_path(repo, &pointer.oid).is_file(),
+                checked_out,
+                path,
+                oid: pointer.oid,
+                size: pointer.size,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    Ok(LfsStatus {
+        enabled: !files.is_empty() || repo.path().join("lfs").is_dir(),
+        files,
+    })
+}
+
+#[command]
+pub async fn lfs_pull(
+    window: Window,
+    state: State<'_, RepositoryState>,
+) -> Result<LfsCommandResult, String> {
+    run_lfs(window, "pull", repo_workdir(&state)?).await
+}
+
+#[command]
+pub async fn lfs_fetch(
+    window: Window,
+    state: State<'_, RepositoryState>,
+) -> Result<LfsCommandResult, String> {
+    run_lfs(window, "fetch", repo_workdir(&state)?).await
+}
+
+fn repo_workdir(state: &State<'_, RepositoryState>) -> Result<PathBuf, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+    repo.workdir()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| "Repository has no working directory".to_string())
+}
+
+// Anything bigger than a pointer file must be real content, so only small files are read
+fn is_smudged(path: &Path) -> bool {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.len() > MAX_POINTER_SIZE as u64 => true,
+        Ok(_) => fs::read(path)
+            .map(|data| parse_pointer(&data).is_none())
+            .unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+// Objects live under `.git/lfs/objects/ab/cd/abcd...` as laid out by git-lfs
+fn object_path(repo: &Repository, oid: &str) -> PathBuf {
+    repo.path()
+        .join("lfs")
+        .join("objects")
+        .join(&oid[0..2])
+        .join(&oid[2..4])
+        .join(oid)
+}
+
+// Runs `git lfs <operation>` off the async runtime. git-lfs appends one line per transfer
+// update to the file named by GIT_LFS_PROGRESS, which is tailed into `lfs-progress` events.
+// That file lives in the temp directory: creating `.git/lfs` would make the repository look
+// like it uses LFS to `lfs_status`.
+async fn run_lfs(
+    window: Window,
+    operation: &'static str,
+    workdir: PathBuf,
+) -> Result<LfsCommandResult, String> {
+    let handle = tauri::async_runtime::spawn_blocking(move || {
+        let progress_path = std::env::temp_dir().join(format!(
+            "gittide-lfs-progress-{}-{}",
+            std::process::id(),
+            NEXT_PROGRESS_FILE.fetch_add(1, Ordering::SeqCst)
+        ));
+        fs::write(&progress_path, "").map_err(|e| e.to_string())?;
+
+        let done = Arc::new(AtomicBool::new(false));
+        let watcher = {
+            let done = done.clone();
+            let path = progress_path.clone();
+            thread::spawn(move || tail_progress(&window, operation, &path, &done))
+        };
+
+        let output = Command::new("git")
+            .args(["lfs", operation])
+            .current_dir(&workdir)
+            .env("GIT_LFS_PROGRESS", &progress_path)
+            .stdin(Stdio::null())
+            .output();
+
+        done.store(true, Ordering::SeqCst);
+        let _ = watcher.join();
+        let _ = fs::remove_file(&progress_path);
+
+        let output = output.map_err(|e| format!("Failed to run git lfs: {}", e))?;
+        let mut text = String::from_utf8_lossy(&output.stdout).to_string();
+        text.push_str(&String::from_utf8_lossy(&output.stderr));
+
+        Ok(LfsCommandResult {
+            success: output.status.success(),
+            output: text,
+        })
+    });
+
+    handle.await.map_err(|e| e.to_string())?
+}
+
+fn tail_progress(window: &Window, operation: &str, path: &Path, done: &AtomicBool) {
+    let mut reader = match fs::File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(_) => return,
+    };
+    let mut line = String::new();
+
+    loop {
+        // Check before reading so the final lines are still drained after the process exits
+        let finished = done.load(Ordering::SeqCst);
+        loop {
+            line.clear();
+            match reader.read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) => {
+                    if let Some(progress) = parse_progress(operation, line.trim_end()) {
+                        let _ = window.emit("lfs-progress", progress);
+                    }
+                }
+            }
+        }
+        if finished {
+            break;
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+}
+
+// Lines look like `download 1/3 1048576/2097152 assets/video.mp4`
+fn parse_progress(operation: &str, line: &str) -> Option<LfsProgress> {
+    let mut parts = line.splitn(4, ' ');
+    let direction = parts.next()?;
+    let (current_file, total_files) = parts.next()?.split_once('/')?;
+    let (bytes_done, bytes_total) = parts.next()?.split_once('/')?;
+
+    Some(LfsProgress {
+        operation: operation.to_string(),
+        direction: direction.to_string(),
+        current_file: current_file.parse().ok()?,
+        total_files: total_files.parse().ok()?,
+        bytes_done: bytes_done.parse().ok()?,
+        bytes_total: bytes_total.parse().ok()?,
+        name: parts.next().unwrap_or("").to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OID: &str = "4d7a214614ab2935c943f9e0ff69d22eadbb8f32b1258daaa5e2ca24d17e2393";
+
+    fn pointer(oid: &str, size: &str) -> String {
+        format!("{}\noid sha256:{}\nsize {}\n", POINTER_VERSION, oid, size)
+    }
+
+    #[test]
+    fn parses_a_pointer_file() {
+        let parsed = parse_pointer(pointer(OID, "12345").as_bytes()).unwrap();
+        assert_eq!(parsed.oid, OID);
+        assert_eq!(parsed.size, 12345);
+
+        // Extension lines are allowed between the version and the oid
+        let text = format!(
+            "{}\next-0-foo sha256:{}\noid sha256:{}\nsize 1\n",
+            POINTER_VERSION, OID, OID
+        );
+        assert_eq!(parse_pointer(text.as_bytes()).unwrap().size, 1);
+    }
+
+    #[test]
+    fn rejects_anything_else() {
+        assert!(parse_pointer(b"").is_none());
+        assert!(parse_pointer(b"just some text\n").is_none());
+        assert!(parse_pointer(pointer(&OID[1..], "1").as_bytes()).is_none());
+        assert!(parse_pointer(pointer(&OID.replace('4', "g"), "1").as_bytes()).is_none());
+        assert!(parse_pointer(pointer(OID, "big").as_bytes()).is_none());
+        assert!(parse_pointer(format!("{}\nsize 1\n", POINTER_VERSION).as_bytes()).is_none());
+        // The version line has to come first
+        let text = format!("oid sha256:{}\n{}\nsize 1\n", OID, POINTER_VERSION);
+        assert!(parse_pointer(text.as_bytes()).is_none());
+
+        let mut large = pointer(OID, "1").into_bytes();
+        large.resize(MAX_POINTER_SIZE + 1, b'\n');
+        assert!(parse_pointer(&large).is_none());
+    }
+
+    #[test]
+    fn parses_progress_lines() {
+        let progress =
+            parse_progress("pull", "download 1/3 1048576/2097152 assets/my video.mp4").unwrap();
+        assert_eq!(progress.operation, "pull");
+        assert_eq!(progress.direction, "download");
+        assert_eq!((progress.current_file, progress.total_files), (1, 3));
+        assert_eq!(
+            (progress.bytes_done, progress.bytes_total),
+            (1048576, 2097152)
+        );
+        assert_eq!(progress.name, "assets/my video.mp4");
+
+        let progress = parse_progress("fetch", "checkout 2/2 10/10").unwrap();
+        assert_eq!(progress.name, "");
+    }
+
+    #[test]
+    fn skips_malformed_progress_lines() {
+        assert!(parse_progress("pull", "").is_none());
+        assert!(parse_progress("pull", "download 1/3").is_none());
+        assert!(parse_progress("pull", "download 1-3 10/20 a").is_none());
+        assert!(parse_progress("pull", "download x/3 10/20 a").is_none());
+        assert!(parse_progress("pull", "download 1/3 10/many a").is_none());
+    }
+}
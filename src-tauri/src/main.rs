@@ -1,11 +1,31 @@
-use git2::{Repository, Branch, BranchType, Commit, Reference, Oid, Sort, Status};
+use git2::{Repository, Branch, BranchType, Commit, Object, Reference, Oid, Sort, Status};
 use serde::{Serialize, Deserialize};
-use tauri::{command, plugin::{Builder, TauriPlugin}, Manager, State};
+use tauri::{command, plugin::{Builder, TauriPlugin}, Manager, State, Window};
 use std::sync::Mutex;
 use std::collections::HashMap;
 use chrono::{DateTime, TimeZone, Utc};
 use thiserror::Error;
 
+mod app_state;
+mod archive;
+mod bisect;
+mod commit;
+mod compare;
+mod diff;
+mod graph;
+mod hooks;
+mod lfs;
+mod message;
+mod patch;
+mod stats;
+mod terminal;
+mod tree;
+
+use app_state::AppStore;
+use graph::{GraphBuilder, GraphRow};
+use stats::{compute_commit_stats, spawn_stats_jobs, StatsCache, StatsInFlight};
+use terminal::TerminalState;
+
 // Extended error types
 #[derive(Debug, Error)]
 pub enum GitError {
@@ -37,11 +57,12 @@ pub struct ExtendedCommitInfo {
     position: i32,
     #[serde(rename = "type")]
     commit_type: String,
-    stats: CommitStats,
+    stats: Option<CommitStats>,
     refs: Vec<String>,
+    graph: GraphRow,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitStats {
     files_changed: usize,
     insertions: usize,
@@ -77,10 +98,15 @@ struct RepositoryState(Mutex<Option<Repository>>);
 async fn open_repository(
     path: String,
     state: State<'_, RepositoryState>,
+    store: State<'_, AppStore>,
 ) -> Result<RepoStatus, String> {
     let repo = Repository::open(&path).map_err(|e| e.to_string())?;
     let status = get_repo_status(&repo)?;
+    // Recorded by the repository's own top-level directory, whichever subfolder was picked
+    let root = repo.workdir().unwrap_or_else(|| repo.path()).to_path_buf();
     *state.0.lock().unwrap() = Some(repo);
+    // Failing to persist the recent list shouldn't keep the repository from opening
+    let _ = store.record_open(&root.to_string_lossy(), &status.current_branch);
     Ok(status)
 }
 
@@ -124,7 +150,10 @@ async fn get_branches(
 
 #[command]
 async fn get_git_history(
-    state: State<'_, RepositoryState>
+    include_stats: Option<bool>,
+    window: Window,
+    state: State<'_, RepositoryState>,
+    stats_cache: State<'_, StatsCache>,
 ) -> Result<Vec<ExtendedCommitInfo>, String> {
     let repo = state.0.lock().unwrap();
     let repo = repo.as_ref().ok_or("No repository opened")?;
@@ -133,20 +162,13 @@ async fn get_git_history(
     revwalk.set_sorting(Sort::TOPOLOGICAL | Sort::TIME).map_err(|e| e.to_string())?;
     revwalk.push_head().map_err(|e| e.to_string())?;
     
+    let include_stats = include_stats.unwrap_or(true);
     let mut commits = Vec::new();
-    let mut branch_positions = HashMap::new();
-    let mut next_position = 0;
+    let mut graph = GraphBuilder::default();
+    let mut missing_stats = Vec::new();
 
     // Get all references for labeling
-    let refs: HashMap<Oid, Vec<String>> = repo
-        .references()
-        .map_err(|e| e.to_string())?
-        .filter_map(Result::ok)
-        .filter_map(|r| r.target().map(|oid| (oid, r.name().unwrap_or("").to_string())))
-        .fold(HashMap::new(), |mut acc, (oid, name)| {
-            acc.entry(oid).or_insert_with(Vec::new).push(name);
-            acc
-        });
+    let refs = get_refs_by_target(repo)?;
 
     for oid_result in revwalk.take(100) {
         let oid = oid_result.map_err(|e| e.to_string())?;
@@ -155,34 +177,18 @@ async fn get_git_history(
         let branch_name = get_branch_for_commit(repo, &commit)
             .unwrap_or_else(|_| "detached".to_string());
         
-        let position = *branch_positions
-            .entry(branch_name.clone())
-            .or_insert_with(|| {
-                let pos = next_position;
-                next_position += 1;
-                pos
-            });
-
-        let stats = if let Ok(parent) = commit.parent(0) {
-            let diff = repo
-                .diff_tree_to_tree(
-                    Some(&parent.tree().unwrap()),
-                    Some(&commit.tree().unwrap()),
-                    None,
-                )
-                .unwrap();
-            let stats = diff.stats().unwrap();
-            CommitStats {
-                files_changed: stats.files_changed(),
-                insertions: stats.insertions(),
-                deletions: stats.deletions(),
+        let parent_ids: Vec<Oid> = commit.parent_ids().collect();
+        let row = graph.next_row(oid, &parent_ids);
+
+        // Cached stats are returned inline; the rest stream in via `commit-stats` events
+        let stats = if include_stats {
+            let cached = stats_cache.0.lock().unwrap().get(&oid).cloned();
+            if cached.is_none() {
+                missing_stats.push(oid);
             }
+            cached
         } else {
-            CommitStats {
-                files_changed: 0,
-                insertions: 0,
-                deletions: 0,
-            }
+            None
         };
 
         commits.push(ExtendedCommitInfo {
@@ -194,18 +200,41 @@ async fn get_git_history(
             committer_email: commit.committer().email().unwrap_or("").to_string(),
             branch: branch_name,
             timestamp: format_timestamp(commit.time()),
-            parents: commit.parent_ids().map(|oid| oid.to_string()).collect(),
-            color: get_commit_color(position),
-            position: position as i32,
+            parents: parent_ids.iter().map(|oid| oid.to_string()).collect(),
+            color: row.color.clone(),
+            position: row.lane as i32,
             commit_type: if commit.parent_count() > 1 { "merge" } else { "commit" }.to_string(),
             stats,
             refs: refs.get(&oid).cloned().unwrap_or_default(),
+            graph: row,
         });
     }
 
+    spawn_stats_jobs(window, repo.path().to_path_buf(), missing_stats);
+
     Ok(commits)
 }
 
+#[command]
+async fn get_commit_stats(
+    oid: String,
+    state: State<'_, RepositoryState>,
+    stats_cache: State<'_, StatsCache>,
+) -> Result<CommitStats, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let oid = Oid::from_str(&oid).map_err(|e| e.to_string())?;
+    if let Some(stats) = stats_cache.0.lock().unwrap().get(&oid) {
+        return Ok(stats.clone());
+    }
+
+    let commit = repo.find_commit(oid).map_err(|e| e.to_string())?;
+    let stats = compute_commit_stats(repo, &commit).map_err(|e| e.to_string())?;
+    stats_cache.0.lock().unwrap().insert(oid, stats.clone());
+    Ok(stats)
+}
+
 // Helper function to get repository status
 fn get_repo_status(repo: &Repository) -> Result<RepoStatus, String> {
     let head = repo.head().ok();
@@ -246,6 +275,36 @@ fn get_repo_status(repo: &Repository) -> Result<RepoStatus, String> {
     })
 }
 
+// Maps each commit to the references pointing at it, peeling annotated tags to their commit
+fn get_refs_by_target(repo: &Repository) -> Result<HashMap<Oid, Vec<String>>, String> {
+    let refs = repo
+        .references()
+        .map_err(|e| e.to_string())?
+        .filter_map(Result::ok)
+        .filter_map(|r| {
+            let oid = r.peel_to_commit().map(|c| c.id()).ok().or_else(|| r.target())?;
+            Some((oid, r.name().unwrap_or("").to_string()))
+        })
+        .fold(HashMap::new(), |mut acc, (oid, name)| {
+            acc.entry(oid).or_insert_with(Vec::new).push(name);
+            acc
+        });
+    Ok(refs)
+}
+
+// Looks up anything `git rev-parse` accepts: branch and tag names, oids, `HEAD~2` and so on
+fn resolve_revision<'r>(repo: &'r Repository, rev: &str) -> Result<Object<'r>, String> {
+    repo.revparse_single(rev)
+        .map_err(|e| format!("Cannot resolve revision '{}': {}", rev, e.message()))
+}
+
+fn resolve_commit(repo: &Repository, rev: &str) -> Result<Oid, String> {
+    resolve_revision(repo, rev)?
+        .peel_to_commit()
+        .map(|commit| commit.id())
+        .map_err(|e| format!("Revision '{}' is not a commit: {}", rev, e.message()))
+}
+
 // Helper functions remain the same
 fn get_commit_color(index: usize) -> String {
     let colors = vec![
@@ -299,9 +358,49 @@ pub fn init() -> TauriPlugin<tauri::Wry> {
             open_repository,
             get_branches,
             get_git_history,
+            get_commit_stats,
+            tree::get_file_at_revision,
+            tree::list_tree,
+            patch::export_patch,
+            patch::apply_patch,
+            commit::create_commit,
+            commit::get_commit_details,
+            diff::get_commit_diff,
+            diff::get_working_diff,
+            archive::export_archive,
+            terminal::create_pty,
+            terminal::write_to_pty,
+            terminal::resize_pty,
+            terminal::close_pty,
+            terminal::list_ptys,
+            hooks::list_hooks,
+            hooks::run_hook,
+            lfs::lfs_status,
+            lfs::lfs_pull,
+            lfs::lfs_fetch,
+            app_state::get_recent_repositories,
+            app_state::pin_repository,
+            app_state::remove_recent,
+            app_state::save_repository_layout,
+            compare::compare_refs,
+            bisect::bisect_start,
+            bisect::bisect_mark,
+            bisect::bisect_status,
+            bisect::bisect_reset,
+            message::get_commit_template,
+            message::list_commit_templates,
+            message::save_commit_template,
+            message::remove_commit_template,
+            message::get_commit_lint_rules,
+            message::save_commit_lint_rules,
+            message::validate_commit_message,
         ])
         .setup(|app| {
             app.manage(RepositoryState(Mutex::new(None)));
+            app.manage(StatsCache(Mutex::new(HashMap::new())));
+            app.manage(StatsInFlight::default());
+            app.manage(TerminalState::default());
+            app.manage(AppStore::load(app.path_resolver().app_data_dir()));
             Ok(())
         })
         .build()
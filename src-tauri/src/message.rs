@@ -0,0 +1,531 @@
+use git2::{message_prettify, message_trailers_strs, Repository};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use tauri::{command, State};
+
+use crate::app_state::AppStore;
+use crate::RepositoryState;
+
+const DEFAULT_TYPES: &[&str] = &[
+    "build", "chore", "ci", "docs", "feat", "fix", "perf", "refactor", "revert", "style", "test",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitTemplate {
+    pub name: String,
+    // "config" for the template named by `commit.template`, "app" for ones saved in GitTide
+    source: String,
+    content: String,
+}
+
+// Limits of 0 turn the corresponding check off
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LintRules {
+    max_subject_length: usize,
+    conventional_commits: bool,
+    allowed_types: Vec<String>,
+    no_trailing_period: bool,
+    body_wrap_width: usize,
+}
+
+impl Default for LintRules {
+    fn default() -> Self {
+        LintRules {
+            max_subject_length: 72,
+            conventional_commits: false,
+            allowed_types: DEFAULT_TYPES.iter().map(|t| t.to_string()).collect(),
+            no_trailing_period: true,
+            body_wrap_width: 72,
+        }
+    }
+}
+
+// A `Key: value` line from the block at the end of a message, e.g. `Co-authored-by`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Trailer {
+    pub key: String,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Violation {
+    rule: String,
+    line: usize,
+    message: String,
+}
+
+// The repository's `commit.template` wins, as it does for `git commit`; a name picks one of
+// the templates saved in the app instead
+#[command]
+pub async fn get_commit_template(
+    name: Option<String>,
+    state: State<'_, RepositoryState>,
+    store: State<'_, AppStore>,
+) -> Result<Option<CommitTemplate>, String> {
+    if let Some(name) = name {
+        return store
+            .commit_templates()
+            .into_iter()
+            .find(|t| t.name == name)
+            .map(Some)
+            .ok_or_else(|| format!("Unknown commit template: {}", name));
+    }
+
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+    config_template(repo)
+}
+
+#[command]
+pub async fn list_commit_templates(
+    store: State<'_, AppStore>,
+) -> Result<Vec<CommitTemplate>, String> {
+    Ok(store.commit_templates())
+}
+
+#[command]
+pub async fn save_commit_template(
+    name: String,
+    content: String,
+    store: State<'_, AppStore>,
+) -> Result<(), String> {
+    if name.trim().is_empty() {
+        return Err("Template name cannot be empty".to_string());
+    }
+    store.save_commit_template(CommitTemplate {
+        name,
+        source: "app".to_string(),
+        content,
+    })
+}
+
+#[command]
+pub async fn remove_commit_template(
+    name: String,
+    store: State<'_, AppStore>,
+) -> Result<(), String> {
+    store.remove_commit_template(&name)
+}
+
+#[command]
+pub async fn get_commit_lint_rules(store: State<'_, AppStore>) -> Result<LintRules, String> {
+    Ok(store.lint_rules())
+}
+
+#[command]
+pub async fn save_commit_lint_rules(
+    rules: LintRules,
+    store: State<'_, AppStore>,
+) -> Result<(), String> {
+    store.set_lint_rules(rules)
+}
+
+// Checks against the saved rules unless the caller passes its own, e.g. to preview an edit.
+// `strip_comments` matches the option of the same name on `create_commit`.
+#[command]
+pub async fn validate_commit_message(
+    message: String,
+    rules: Option<LintRules>,
+    strip_comments: Option<bool>,
+    store: State<'_, AppStore>,
+) -> Result<Vec<Violation>, String> {
+    let rules = rules.unwrap_or_else(|| store.lint_rules());
+    let comment_char = if strip_comments.unwrap_or(false) {
+        Some(b'#')
+    } else {
+        None
+    };
+    let message = message_prettify(message, comment_char).map_err(|e| e.to_string())?;
+    Ok(lint(&message, &rules))
+}
+
+fn config_template(repo: &Repository) -> Result<Option<CommitTemplate>, String> {
+    let path = match repo
+        .config()
+        .and_then(|config| config.get_path("commit.template"))
+    {
+        Ok(path) => path,
+        Err(_) => return Ok(None),
+    };
+    // Relative paths are taken from the top of the work tree
+    let path = if path.is_absolute() {
+        path
+    } else {
+        repo.workdir().unwrap_or_else(|| repo.path()).join(path)
+    };
+
+    let content = fs::read_to_string(&path)
+        .map_err(|e| format!("Could not read commit template {}: {}", path.display(), e))?;
+    Ok(Some(CommitTemplate {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        source: "config".to_string(),
+        content,
+    }))
+}
+
+pub fn parse_trailers(message: &str) -> Vec<Trailer> {
+    message_trailers_strs(message)
+        .map(|trailers| {
+            trailers
+                .iter()
+                .map(|(key, value)| Trailer {
+                    key: key.to_string(),
+                    value: value.to_string(),
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// A body whose last paragraph is the trailer block, without that paragraph
+pub fn strip_trailers(body: &str) -> &str {
+    let body = body.trim_end();
+    if parse_trailers(&format!("subject\n\n{}", body)).is_empty() {
+        return body;
+    }
+    match body.rsplit_once("\n\n") {
+        Some((rest, _)) => rest.trim_end(),
+        None => "",
+    }
+}
+
+// Appends trailers the way `git commit --trailer` does: into the existing trailer block when
+// the message ends with one, otherwise as a new paragraph. Exact duplicates are dropped.
+pub fn append_trailers(message: &str, trailers: &[Trailer]) -> Result<String, String> {
+    let mut message = message.trim_end().to_string();
+    let mut existing = parse_trailers(&message);
+    let has_block = !existing.is_empty();
+
+    let mut lines = Vec::new();
+    for trailer in trailers {
+        let key = trailer.key.trim();
+        let value = trailer.value.trim();
+        if key.is_empty() || key.contains(|c: char| c == ':' || c.is_whitespace()) {
+            return Err(format!("Invalid trailer key: '{}'", trailer.key));
+        }
+        if value.is_empty() || value.contains('\n') {
+            return Err(format!("Trailer {} needs a single-line value", key));
+        }
+
+        let duplicate = existing
+            .iter()
+            .any(|t| t.key.eq_ignore_ascii_case(key) && t.value == value);
+        if !duplicate {
+            lines.push(format!("{}: {}", key, value));
+            existing.push(Trailer {
+                key: key.to_string(),
+                value: value.to_string(),
+            });
+        }
+    }
+
+    if lines.is_empty() {
+        return Ok(format!("{}\n", message));
+    }
+    message.push_str(if has_block { "\n" } else { "\n\n" });
+    message.push_str(&lines.join("\n"));
+    message.push('\n');
+    Ok(message)
+}
+
+// Expects a message that has already been through `message_prettify`
+pub fn lint(message: &str, rules: &LintRules) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let mut push = |rule: &str, line: usize, message: String| {
+        violations.push(Violation {
+            rule: rule.to_string(),
+            line,
+            message,
+        })
+    };
+
+    let mut lines = message.lines();
+    let subject = lines.next().unwrap_or("");
+    if subject.trim().is_empty() {
+        push("subject-empty", 1, "Subject line is empty".to_string());
+    }
+
+    let length = subject.chars().count();
+    if rules.max_subject_length > 0 && length > rules.max_subject_length {
+        push(
+            "subject-length",
+            1,
+            format!(
+                "Subject is {} characters; the limit is {}",
+                length, rules.max_subject_length
+            ),
+        );
+    }
+    if rules.no_trailing_period && subject.ends_with('.') {
+        push(
+            "subject-trailing-period",
+            1,
+            "Subject should not end with a period".to_string(),
+        );
+    }
+    if rules.conventional_commits {
+        if let Err(reason) = check_conventional(subject, &rules.allowed_types) {
+            push("conventional-commits", 1, reason);
+        }
+    }
+
+    if lines.next().map(|line| !line.is_empty()).unwrap_or(false) {
+        push(
+            "body-separator",
+            2,
+            "Separate the subject from the body with a blank line".to_string(),
+        );
+    }
+
+    if rules.body_wrap_width > 0 {
+        for (i, line) in message.lines().enumerate().skip(1) {
+            let length = line.chars().count();
+            // A single long token such as a URL cannot be wrapped, so it is left alone
+            if length > rules.body_wrap_width && line.trim().contains(char::is_whitespace) {
+                push(
+                    "body-line-length",
+                    i + 1,
+                    format!(
+                        "Line is {} characters; wrap the body at {}",
+                        length, rules.body_wrap_width
+                    ),
+                );
+            }
+        }
+    }
+
+    violations
+}
+
+// `type(scope)!: description`, where the scope and the breaking-change marker are optional
+fn check_conventional(subject: &str, allowed_types: &[String]) -> Result<(), String> {
+    let (header, description) = subject
+        .split_once(": ")
+        .ok_or("Subject should look like `type(scope): description`")?;
+    if description.trim().is_empty() {
+        return Err("Subject has no description after the type".to_string());
+    }
+
+    let header = header.strip_suffix('!').unwrap_or(header);
+    let kind = match header.split_once('(') {
+        Some((kind, scope)) => {
+            let scope = scope
+                .strip_suffix(')')
+                .ok_or("Scope is missing its closing parenthesis")?;
+            if scope.is_empty() || scope.contains(['(', ')']) {
+                return Err("Scope should be a single word in parentheses".to_string());
+            }
+            kind
+        }
+        None => header,
+    };
+
+    if kind.is_empty() || !kind.chars().all(|c| c.is_ascii_lowercase()) {
+        return Err(format!("'{}' is not a valid commit type", kind));
+    }
+    if !allowed_types.is_empty() && !allowed_types.iter().any(|t| t == kind) {
+        return Err(format!(
+            "'{}' is not one of the allowed types: {}",
+            kind,
+            allowed_types.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn types() -> Vec<String> {
+        DEFAULT_TYPES.iter().map(|t| t.to_string()).collect()
+    }
+
+    fn rules_hit(message: &str, rules: &LintRules) -> Vec<String> {
+        lint(message, rules).into_iter().map(|v| v.rule).collect()
+    }
+
+    #[test]
+    fn accepts_conventional_subjects() {
+        for subject in [
+            "feat: add a thing",
+            "fix(parser): handle empty input",
+            "refactor!: drop the old API",
+            "feat(api)!: rename endpoints",
+        ] {
+            assert_eq!(check_conventional(subject, &types()), Ok(()), "{}", subject);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_conventional_subjects() {
+        for subject in [
+            "add a thing",
+            "feat:missing space",
+            "feat: ",
+            "feat(: unclosed",
+            "feat(): empty scope",
+            "feat(a)(b): two scopes",
+            "Feat: capitalised type",
+            ": no type",
+        ] {
+            assert!(
+                check_conventional(subject, &types()).is_err(),
+                "{}",
+                subject
+            );
+        }
+    }
+
+    #[test]
+    fn only_allowed_types_pass() {
+        let err = check_conventional("wip: half done", &types()).unwrap_err();
+        assert!(err.contains("allowed types"));
+        // An empty list allows any lowercase type
+        assert_eq!(check_conventional("wip: half done", &[]), Ok(()));
+    }
+
+    #[test]
+    fn lints_the_subject_line() {
+        let rules = LintRules::default();
+        assert!(rules_hit("Add a thing\n", &rules).is_empty());
+        assert_eq!(rules_hit("", &rules), vec!["subject-empty"]);
+        assert_eq!(
+            rules_hit("Add a thing.", &rules),
+            vec!["subject-trailing-period"]
+        );
+        assert_eq!(rules_hit(&"a".repeat(73), &rules), vec!["subject-length"]);
+
+        let rules = LintRules {
+            conventional_commits: true,
+            ..LintRules::default()
+        };
+        assert_eq!(
+            rules_hit("Add a thing", &rules),
+            vec!["conventional-commits"]
+        );
+        assert!(rules_hit("feat: add a thing", &rules).is_empty());
+    }
+
+    #[test]
+    fn lints_the_body() {
+        let rules = LintRules::default();
+        let violations = lint("Subject\nNo blank line", &rules);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            (violations[0].rule.as_str(), violations[0].line),
+            ("body-separator", 2)
+        );
+
+        let long_line = "word ".repeat(20);
+        let violations = lint(&format!("Subject\n\nShort\n{}", long_line), &rules);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(
+            (violations[0].rule.as_str(), violations[0].line),
+            ("body-line-length", 4)
+        );
+
+        let url = format!("https://example.com/{}", "a".repeat(80));
+        assert!(lint(&format!("Subject\n\n{}", url), &rules).is_empty());
+    }
+
+    #[test]
+    fn zero_limits_turn_checks_off() {
+        let rules = LintRules {
+            max_subject_length: 0,
+            body_wrap_width: 0,
+            ..LintRules::default()
+        };
+        let long = "word ".repeat(30);
+        assert!(lint(&format!("{}\n\n{}", long.trim(), long), &rules).is_empty());
+    }
+
+    fn trailer(key: &str, value: &str) -> Trailer {
+        Trailer {
+            key: key.to_string(),
+            value: value.to_string(),
+        }
+    }
+
+    fn pairs(trailers: &[Trailer]) -> Vec<(&str, &str)> {
+        trailers
+            .iter()
+            .map(|t| (t.key.as_str(), t.value.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn appended_trailers_parse_back() {
+        let added = [
+            trailer("Co-authored-by", "Ada <ada@example.com>"),
+            trailer("Reviewed-by", "Bo <bo@example.com>"),
+        ];
+        let message = append_trailers("Subject\n\nBody text.\n\n", &added).unwrap();
+        assert_eq!(
+            message,
+            "Subject\n\nBody text.\n\nCo-authored-by: Ada <ada@example.com>\nReviewed-by: Bo <bo@example.com>\n"
+        );
+        assert_eq!(pairs(&parse_trailers(&message)), pairs(&added));
+
+        let body = message.split_once("\n\n").unwrap().1;
+        assert_eq!(strip_trailers(body), "Body text.");
+    }
+
+    #[test]
+    fn appends_into_an_existing_block() {
+        let message = "Subject\n\nBody\n\nSigned-off-by: Ada <ada@example.com>\n";
+        let message = append_trailers(message, &[trailer("Acked-by", "Bo")]).unwrap();
+        assert_eq!(
+            message,
+            "Subject\n\nBody\n\nSigned-off-by: Ada <ada@example.com>\nAcked-by: Bo\n"
+        );
+        assert_eq!(
+            pairs(&parse_trailers(&message)),
+            vec![
+                ("Signed-off-by", "Ada <ada@example.com>"),
+                ("Acked-by", "Bo")
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_exact_duplicate_trailers() {
+        let message = "Subject\n\nSigned-off-by: Ada <ada@example.com>\n";
+        let result = append_trailers(
+            message,
+            &[
+                trailer("signed-off-by", "Ada <ada@example.com>"),
+                trailer("Acked-by", "Bo"),
+                trailer("Acked-by", "Bo"),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            result,
+            "Subject\n\nSigned-off-by: Ada <ada@example.com>\nAcked-by: Bo\n"
+        );
+        assert_eq!(append_trailers(message, &[]).unwrap(), message);
+    }
+
+    #[test]
+    fn rejects_invalid_trailers() {
+        assert!(append_trailers("Subject", &[trailer("Bad key", "x")]).is_err());
+        assert!(append_trailers("Subject", &[trailer("Key:", "x")]).is_err());
+        assert!(append_trailers("Subject", &[trailer("Key", " ")]).is_err());
+        assert!(append_trailers("Subject", &[trailer("Key", "a\nb")]).is_err());
+    }
+
+    #[test]
+    fn strip_keeps_bodies_without_trailers() {
+        assert_eq!(strip_trailers("Just a body.\n"), "Just a body.");
+        assert_eq!(
+            strip_trailers("Fixes: a typo in the docs, not a trailer\nand more prose"),
+            "Fixes: a typo in the docs, not a trailer\nand more prose"
+        );
+        assert_eq!(strip_trailers("Signed-off-by: Ada <ada@example.com>\n"), "");
+    }
+}
@@ -0,0 +1,409 @@
+use git2::{
+    ApplyLocation, ApplyOptions, Diff, Email, EmailCreateOptions, Index, IndexEntry, IndexTime,
+    Oid, Patch, Repository, Sort, Tree,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{command, State};
+
+use crate::RepositoryState;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FailedHunk {
+    patch_index: usize,
+    path: String,
+    header: String,
+    old_start: u32,
+    new_start: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatchApplyResult {
+    applied: bool,
+    check_only: bool,
+    patch_count: usize,
+    // How many patches were written; short of `patch_count` only if writing failed partway
+    applied_patches: usize,
+    failed_patch: Option<usize>,
+    error: Option<String>,
+    files: Vec<String>,
+    failed_hunks: Vec<FailedHunk>,
+}
+
+#[command]
+pub async fn export_patch(
+    oids: Vec<String>,
+    output_path: Option<String>,
+    state: State<'_, RepositoryState>,
+) -> Result<String, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let selected = oids
+        .iter()
+        .map(|oid| Oid::from_str(oid).map_err(|e| e.to_string()))
+        .collect::<Result<HashSet<_>, String>>()?;
+
+    // Parents before children, the order `git am` has to replay them in. Timestamps cannot be
+    // trusted for this: rebased commits often share one, and clocks drift. The walk stops at the
+    // parents of the oldest selected commits instead of running on to the root.
+    let mut revwalk = repo.revwalk().map_err(|e| e.to_string())?;
+    revwalk
+        .set_sorting(Sort::TOPOLOGICAL | Sort::REVERSE)
+        .map_err(|e| e.to_string())?;
+    for oid in &selected {
+        revwalk.push(*oid).map_err(|e| e.to_string())?;
+        let mut oldest = true;
+        for other in &selected {
+            if other != oid
+                && repo
+                    .graph_descendant_of(*oid, *other)
+                    .map_err(|e| e.to_string())?
+            {
+                oldest = false;
+                break;
+            }
+        }
+        if oldest {
+            let commit = repo.find_commit(*oid).map_err(|e| e.to_string())?;
+            for parent in commit.parent_ids() {
+                revwalk.hide(parent).map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    let mut commits = Vec::with_capacity(selected.len());
+    for oid in revwalk {
+        let oid = oid.map_err(|e| e.to_string())?;
+        if selected.contains(&oid) {
+            commits.push(repo.find_commit(oid).map_err(|e| e.to_string())?);
+        }
+    }
+
+    let mut output = String::new();
+    for (i, commit) in commits.iter().enumerate() {
+        if commit.parent_count() > 1 {
+            return Err(format!("Cannot export merge commit {}", commit.id()));
+        }
+
+        let parent_tree = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree().map_err(|e| e.to_string())?),
+            Err(_) => None,
+        };
+        let tree = commit.tree().map_err(|e| e.to_string())?;
+        let diff = repo
+            .diff_tree_to_tree(parent_tree.as_ref(), Some(&tree), None)
+            .map_err(|e| e.to_string())?;
+
+        let email = Email::from_diff(
+            &diff,
+            i + 1,
+            commits.len(),
+            &commit.id(),
+            commit.summary().unwrap_or(""),
+            commit.body().unwrap_or(""),
+            &commit.author(),
+            &mut EmailCreateOptions::new(),
+        )
+        .map_err(|e| e.to_string())?;
+        output.push_str(&String::from_utf8_lossy(email.as_slice()));
+    }
+
+    if let Some(path) = output_path {
+        fs::write(&path, &output).map_err(|e| format!("Failed to write {}: {}", path, e))?;
+    }
+
+    Ok(output)
+}
+
+#[command]
+pub async fn apply_patch(
+    patch_text: String,
+    to: Option<String>,
+    check_only: Option<bool>,
+    state: State<'_, RepositoryState>,
+) -> Result<PatchApplyResult, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let location = match to.as_deref().unwrap_or("worktree") {
+        "worktree" => ApplyLocation::WorkDir,
+        "index" => ApplyLocation::Index,
+        "both" => ApplyLocation::Both,
+        other => return Err(format!("Unknown apply target: {}", other)),
+    };
+    let check_only = check_only.unwrap_or(false);
+
+    let patches = split_mbox(&patch_text);
+    let diffs = patches
+        .iter()
+        .map(|text| Diff::from_buffer(text.as_bytes()).map_err(|e| e.to_string()))
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut result = PatchApplyResult {
+        applied: false,
+        check_only,
+        patch_count: patches.len(),
+        applied_patches: 0,
+        failed_patch: None,
+        error: None,
+        files: diffs
+            .iter()
+            .flat_map(|diff| delta_paths(diff, false))
+            .map(|p| p.to_string_lossy().to_string())
+            .collect(),
+        failed_hunks: Vec::new(),
+    };
+
+    // The whole series is checked first, each patch against the tree the ones before it
+    // produce, so later patches that build on earlier ones check correctly and a series that
+    // cannot apply leaves nothing half-written. The objects this creates only live in memory.
+    let scratch = scratch_repository(repo)?;
+    let mut tree = baseline_tree(&scratch, repo.workdir(), location, &diffs)?;
+    for (patch_index, diff) in diffs.iter().enumerate() {
+        match scratch.apply_to_tree(&tree, diff, None) {
+            Ok(mut index) => {
+                let tree_id = index.write_tree_to(&scratch).map_err(|e| e.to_string())?;
+                tree = scratch.find_tree(tree_id).map_err(|e| e.to_string())?;
+            }
+            Err(e) => {
+                result.failed_patch = Some(patch_index);
+                result.error = Some(e.message().to_string());
+                result.failed_hunks = find_failed_hunks(&scratch, &tree, diff, patch_index)?;
+                return Ok(result);
+            }
+        }
+    }
+    if check_only {
+        return Ok(result);
+    }
+
+    for (patch_index, diff) in diffs.iter().enumerate() {
+        if let Err(e) = repo.apply(diff, location, None) {
+            // Earlier patches stay applied; `applied_patches` tells the caller how far it got
+            result.failed_patch = Some(patch_index);
+            result.error = Some(format!("Failed to apply patch {}: {}", patch_index + 1, e));
+            return Ok(result);
+        }
+        result.applied_patches += 1;
+    }
+
+    result.applied = true;
+    Ok(result)
+}
+
+// A second handle on the repository that keeps new objects in memory, so checking a series
+// writes nothing to `.git/objects`, just like `git apply --check`
+fn scratch_repository(repo: &Repository) -> Result<Repository, String> {
+    let scratch = Repository::open(repo.path()).map_err(|e| e.to_string())?;
+    // Above the loose and pack backends, so every write lands in the mempack
+    scratch
+        .odb()
+        .and_then(|odb| odb.add_new_mempack_backend(1000).map(|_| ()))
+        .map_err(|e| e.to_string())?;
+    Ok(scratch)
+}
+
+// The tree the series is checked against: the index, or for the work tree the index with every
+// file the series touches replaced by its work tree contents
+fn baseline_tree<'repo>(
+    repo: &'repo Repository,
+    workdir: Option<&Path>,
+    location: ApplyLocation,
+    diffs: &[Diff],
+) -> Result<Tree<'repo>, String> {
+    let index_tree = repo
+        .index()
+        .and_then(|mut index| index.write_tree())
+        .map_err(|e| e.to_string())?;
+    let mut index = Index::new().map_err(|e| e.to_string())?;
+    index
+        .read_tree(&repo.find_tree(index_tree).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+
+    if let ApplyLocation::WorkDir = location {
+        let workdir = workdir.ok_or("Repository has no working directory")?;
+        for path in diffs.iter().flat_map(|diff| delta_paths(diff, true)) {
+            match fs::read(workdir.join(&path)) {
+                Ok(content) => {
+                    let mut entry = index
+                        .get_path(&path, 0)
+                        .unwrap_or_else(|| new_index_entry(&path));
+                    entry.id = repo.blob(&content).map_err(|e| e.to_string())?;
+                    entry.file_size = content.len() as u32;
+                    index.add(&entry).map_err(|e| e.to_string())?;
+                }
+                Err(_) => {
+                    let _ = index.remove_path(&path);
+                }
+            }
+        }
+    }
+
+    let tree_id = index.write_tree_to(repo).map_err(|e| e.to_string())?;
+    repo.find_tree(tree_id).map_err(|e| e.to_string())
+}
+
+// New-side paths of every delta, or both sides with `both_sides` so renames are covered too
+fn delta_paths(diff: &Diff, both_sides: bool) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for delta in diff.deltas() {
+        let new = delta.new_file().path();
+        let old = delta.old_file().path();
+        if let Some(path) = new.or(old) {
+            paths.push(path.to_path_buf());
+        }
+        if both_sides {
+            if let (Some(_), Some(old)) = (new, old) {
+                paths.push(old.to_path_buf());
+            }
+        }
+    }
+    paths
+}
+
+fn new_index_entry(path: &Path) -> IndexEntry {
+    IndexEntry {
+        ctime: IndexTime::new(0, 0),
+        mtime: IndexTime::new(0, 0),
+        dev: 0,
+        ino: 0,
+        mode: 0o100644,
+        uid: 0,
+        gid: 0,
+        file_size: 0,
+        id: Oid::zero(),
+        flags: 0,
+        flags_extended: 0,
+        path: path.to_string_lossy().replace('\\', "/").into_bytes(),
+    }
+}
+
+// Libgit2 rejects a patch as a whole, so each hunk is re-checked on its own to find the culprits
+fn find_failed_hunks(
+    repo: &Repository,
+    tree: &Tree,
+    diff: &Diff,
+    patch_index: usize,
+) -> Result<Vec<FailedHunk>, String> {
+    let mut failed = Vec::new();
+
+    for delta_index in 0..diff.deltas().len() {
+        let patch = match Patch::from_diff(diff, delta_index).map_err(|e| e.to_string())? {
+            Some(patch) => patch,
+            None => continue,
+        };
+        let delta = patch.delta();
+        let path = delta
+            .new_file()
+            .path()
+            .or_else(|| delta.old_file().path())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        for hunk_index in 0..patch.num_hunks() {
+            let (hunk, _) = patch.hunk(hunk_index).map_err(|e| e.to_string())?;
+            let (old_start, new_start) = (hunk.old_start(), hunk.new_start());
+
+            let mut options = ApplyOptions::new();
+            options.delta_callback(|d| {
+                d.and_then(|d| d.new_file().path().or_else(|| d.old_file().path()))
+                    .map(|p| p.to_string_lossy() == path.as_str())
+                    .unwrap_or(false)
+            });
+            options.hunk_callback(|h| {
+                h.map(|h| h.old_start() == old_start && h.new_start() == new_start)
+                    .unwrap_or(false)
+            });
+
+            if repo.apply_to_tree(tree, diff, Some(&mut options)).is_err() {
+                failed.push(FailedHunk {
+                    patch_index,
+                    path: path.clone(),
+                    header: String::from_utf8_lossy(hunk.header())
+                        .trim_end()
+                        .to_string(),
+                    old_start,
+                    new_start,
+                });
+            }
+        }
+    }
+
+    Ok(failed)
+}
+
+// Splits `git format-patch` mbox output into individual patches; plain diffs come back whole
+fn split_mbox(text: &str) -> Vec<&str> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        if is_mbox_separator(line) {
+            starts.push(offset);
+        }
+        offset += line.len();
+    }
+
+    if starts.len() <= 1 {
+        return vec![text];
+    }
+
+    starts
+        .iter()
+        .enumerate()
+        .map(|(i, &start)| {
+            let end = starts.get(i + 1).copied().unwrap_or(text.len());
+            &text[start..end]
+        })
+        .collect()
+}
+
+fn is_mbox_separator(line: &str) -> bool {
+    line.strip_prefix("From ")
+        .and_then(|rest| rest.split(' ').next())
+        .map(|oid| oid.len() == 40 && oid.chars().all(|c| c.is_ascii_hexdigit()))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIRST: &str = "From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001\n\
+                         Subject: [PATCH 1/2] one\n\n---\n a | 1 +\n";
+    const SECOND: &str = "From 2222222222222222222222222222222222222222 Mon Sep 17 00:00:00 2001\n\
+                          Subject: [PATCH 2/2] two\n\n---\n b | 1 +\n";
+
+    #[test]
+    fn splits_a_series_at_each_separator() {
+        let text = format!("{}{}", FIRST, SECOND);
+        assert_eq!(split_mbox(&text), vec![FIRST, SECOND]);
+    }
+
+    #[test]
+    fn keeps_single_patches_and_plain_diffs_whole() {
+        assert_eq!(split_mbox(FIRST), vec![FIRST]);
+
+        let diff = "diff --git a/a b/a\n--- a/a\n+++ b/a\n@@ -1 +1 @@\n-x\n+y\n";
+        assert_eq!(split_mbox(diff), vec![diff]);
+        assert_eq!(split_mbox(""), vec![""]);
+    }
+
+    #[test]
+    fn only_oid_lines_start_a_patch() {
+        // A "From " line in a commit message is not a separator
+        let body = "From the top, this changes things\nFrom abc Mon Sep 17 2001\n";
+        let text = format!("{}{}{}", FIRST, body, SECOND);
+        assert_eq!(
+            split_mbox(&text),
+            vec![format!("{}{}", FIRST, body).as_str(), SECOND]
+        );
+        assert!(!is_mbox_separator(
+            " From 1111111111111111111111111111111111111111 Mon"
+        ));
+        assert!(is_mbox_separator(
+            "From 1111111111111111111111111111111111111111 Mon Sep 17 00:00:00 2001\r\n"
+        ));
+    }
+}
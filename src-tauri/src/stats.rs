@@ -0,0 +1,116 @@
+use git2::{Commit, Oid, Repository};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{Manager, Window};
+
+use crate::CommitStats;
+
+// In-memory stats cache keyed by commit oid; a commit's diff never changes so entries never expire
+pub struct StatsCache(pub Mutex<HashMap<Oid, CommitStats>>);
+
+// Commits whose stats are being computed, so a history refresh does not schedule them again
+#[derive(Default)]
+pub struct StatsInFlight(pub Mutex<HashSet<Oid>>);
+
+// Event payload sent to the frontend as each missing stat is computed. Exactly one of `stats`
+// and `error` is set, so the frontend can stop waiting either way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommitStatsUpdate {
+    id: String,
+    stats: Option<CommitStats>,
+    error: Option<String>,
+}
+
+pub fn compute_commit_stats(
+    repo: &Repository,
+    commit: &Commit,
+) -> Result<CommitStats, git2::Error> {
+    let parent_tree = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent_tree.as_ref(), Some(&commit.tree()?), None)?;
+    let stats = diff.stats()?;
+
+    Ok(CommitStats {
+        files_changed: stats.files_changed(),
+        insertions: stats.insertions(),
+        deletions: stats.deletions(),
+    })
+}
+
+// Computes stats for the given commits on a background thread pool, caching each result
+// and emitting a `commit-stats` event per commit as soon as it is ready. Commits already being
+// computed are left to the job that has them, which sends their event when it finishes.
+pub fn spawn_stats_jobs(window: Window, repo_path: PathBuf, oids: Vec<Oid>) {
+    let mut ready = Vec::new();
+    let oids: Vec<Oid> = {
+        let cache = window.state::<StatsCache>();
+        let cache = cache.0.lock().unwrap();
+        let in_flight = window.state::<StatsInFlight>();
+        let mut in_flight = in_flight.0.lock().unwrap();
+        oids.into_iter()
+            .filter(|oid| match cache.get(oid) {
+                Some(stats) => {
+                    ready.push((*oid, stats.clone()));
+                    false
+                }
+                None => in_flight.insert(*oid),
+            })
+            .collect()
+    };
+    // Finished since the caller looked, after that job had already sent its event
+    for (oid, stats) in ready {
+        emit_stats(&window, oid, Ok(stats));
+    }
+    if oids.is_empty() {
+        return;
+    }
+
+    tauri::async_runtime::spawn_blocking(move || {
+        oids.par_iter().for_each_init(
+            || Repository::open(&repo_path).map_err(|e| e.to_string()),
+            |repo, oid| {
+                let result = repo.as_ref().map_err(|e| e.clone()).and_then(|repo| {
+                    repo.find_commit(*oid)
+                        .and_then(|commit| compute_commit_stats(repo, &commit))
+                        .map_err(|e| e.to_string())
+                });
+
+                if let Ok(stats) = &result {
+                    window
+                        .state::<StatsCache>()
+                        .0
+                        .lock()
+                        .unwrap()
+                        .insert(*oid, stats.clone());
+                }
+                window
+                    .state::<StatsInFlight>()
+                    .0
+                    .lock()
+                    .unwrap()
+                    .remove(oid);
+                emit_stats(&window, *oid, result);
+            },
+        );
+    });
+}
+
+fn emit_stats(window: &Window, oid: Oid, result: Result<CommitStats, String>) {
+    let (stats, error) = match result {
+        Ok(stats) => (Some(stats), None),
+        Err(error) => (None, Some(error)),
+    };
+    let _ = window.emit(
+        "commit-stats",
+        CommitStatsUpdate {
+            id: oid.to_string(),
+            stats,
+            error,
+        },
+    );
+}
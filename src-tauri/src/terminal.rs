@@ -0,0 +1,230 @@
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::{command, Manager, State, Window};
+
+use crate::RepositoryState;
+
+// Each part has its own lock so a slow write to one shell never holds up the session map
+struct TerminalSession {
+    cwd: String,
+    master: Mutex<Box<dyn MasterPty + Send>>,
+    writer: Mutex<Box<dyn Write + Send>>,
+    child: Mutex<Box<dyn Child + Send + Sync>>,
+}
+
+// Open shells keyed by session id, so every repository tab can own its own terminals
+#[derive(Default)]
+pub struct TerminalState {
+    sessions: Mutex<HashMap<u32, Arc<TerminalSession>>>,
+    next_id: AtomicU32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TerminalInfo {
+    session_id: u32,
+    cwd: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PtyOutput {
+    session_id: u32,
+    data: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PtyExit {
+    session_id: u32,
+    exit_code: Option<u32>,
+}
+
+#[command]
+pub async fn create_pty(
+    cwd: Option<String>,
+    rows: Option<u16>,
+    cols: Option<u16>,
+    window: Window,
+    state: State<'_, RepositoryState>,
+    terminals: State<'_, TerminalState>,
+) -> Result<u32, String> {
+    // Shells start in the open repository unless the caller asks for somewhere else, and in
+    // the user's home directory when there is no repository to start in
+    let cwd = match cwd {
+        Some(cwd) => cwd,
+        None => {
+            let repo = state.0.lock().unwrap();
+            let workdir = repo
+                .as_ref()
+                .and_then(|repo| repo.workdir())
+                .map(|dir| dir.to_string_lossy().to_string());
+            match workdir {
+                Some(dir) => dir,
+                None => home_dir().ok_or("Could not find the home directory")?,
+            }
+        }
+    };
+
+    let pair = native_pty_system()
+        .openpty(PtySize {
+            rows: rows.unwrap_or(24),
+            cols: cols.unwrap_or(80),
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut cmd = CommandBuilder::new_default_prog();
+    cmd.cwd(&cwd);
+    cmd.env("TERM", "xterm-256color");
+    let child = pair.slave.spawn_command(cmd).map_err(|e| e.to_string())?;
+    // The slave end belongs to the shell now; keeping it open would stop EOF from arriving
+    drop(pair.slave);
+
+    let reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
+    let writer = pair.master.take_writer().map_err(|e| e.to_string())?;
+
+    let session_id = terminals.next_id.fetch_add(1, Ordering::SeqCst);
+    terminals.sessions.lock().unwrap().insert(
+        session_id,
+        Arc::new(TerminalSession {
+            cwd,
+            master: Mutex::new(pair.master),
+            writer: Mutex::new(writer),
+            child: Mutex::new(child),
+        }),
+    );
+
+    thread::spawn(move || stream_output(window, session_id, reader));
+
+    Ok(session_id)
+}
+
+#[command]
+pub async fn write_to_pty(
+    session_id: u32,
+    input: String,
+    terminals: State<'_, TerminalState>,
+) -> Result<(), String> {
+    let session = find_session(&terminals, session_id)?;
+    let mut writer = session.writer.lock().unwrap();
+    writer
+        .write_all(input.as_bytes())
+        .and_then(|_| writer.flush())
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn resize_pty(
+    session_id: u32,
+    rows: u16,
+    cols: u16,
+    terminals: State<'_, TerminalState>,
+) -> Result<(), String> {
+    let session = find_session(&terminals, session_id)?;
+    let master = session.master.lock().unwrap();
+    master
+        .resize(PtySize {
+            rows,
+            cols,
+            pixel_width: 0,
+            pixel_height: 0,
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[command]
+pub async fn close_pty(session_id: u32, terminals: State<'_, TerminalState>) -> Result<(), String> {
+    let session = terminals.sessions.lock().unwrap().remove(&session_id);
+    if let Some(session) = session {
+        let mut child = session.child.lock().unwrap();
+        child.kill().map_err(|e| e.to_string())?;
+        // The output thread no longer finds the session, so the shell is reaped here
+        let _ = child.wait();
+    }
+    Ok(())
+}
+
+// The map lock is only held long enough to clone the handle
+fn find_session(
+    terminals: &TerminalState,
+    session_id: u32,
+) -> Result<Arc<TerminalSession>, String> {
+    terminals
+        .sessions
+        .lock()
+        .unwrap()
+        .get(&session_id)
+        .cloned()
+        .ok_or_else(|| format!("Terminal session {} not found", session_id))
+}
+
+#[command]
+pub async fn list_ptys(
+    cwd: Option<String>,
+    terminals: State<'_, TerminalState>,
+) -> Result<Vec<TerminalInfo>, String> {
+    let sessions = terminals.sessions.lock().unwrap();
+    let mut list: Vec<TerminalInfo> = sessions
+        .iter()
+        .filter(|(_, session)| cwd.as_ref().map(|c| *c == session.cwd).unwrap_or(true))
+        .map(|(id, session)| TerminalInfo {
+            session_id: *id,
+            cwd: session.cwd.clone(),
+        })
+        .collect();
+    list.sort_by_key(|info| info.session_id);
+    Ok(list)
+}
+
+// Forwards shell output until the pty closes, then reports the exit and drops the session
+fn stream_output(window: Window, session_id: u32, mut reader: Box<dyn Read + Send>) {
+    // A streaming decoder keeps multi-byte characters intact when they straddle two reads
+    let mut decoder = encoding_rs::UTF_8.new_decoder();
+    let mut buffer = [0u8; 4096];
+
+    loop {
+        let read = match reader.read(&mut buffer) {
+            Ok(0) | Err(_) => 0,
+            Ok(n) => n,
+        };
+
+        let capacity = decoder.max_utf8_buffer_length(read).unwrap_or(read * 3 + 4);
+        let mut data = String::with_capacity(capacity);
+        let _ = decoder.decode_to_string(&buffer[..read], &mut data, read == 0);
+        if !data.is_empty() {
+            let _ = window.emit("pty-output", PtyOutput { session_id, data });
+        }
+        if read == 0 {
+            break;
+        }
+    }
+
+    let session = window
+        .state::<TerminalState>()
+        .sessions
+        .lock()
+        .unwrap()
+        .remove(&session_id);
+    let exit_code = session
+        .and_then(|session| session.child.lock().unwrap().wait().ok())
+        .map(|status| status.exit_code());
+
+    let _ = window.emit(
+        "pty-exit",
+        PtyExit {
+            session_id,
+            exit_code,
+        },
+    );
+}
+
+fn home_dir() -> Option<String> {
+    std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .ok()
+        .filter(|dir| !dir.is_empty())
+}
@@ -0,0 +1,169 @@
+use encoding_rs::{Encoding, UTF_8};
+use git2::{ObjectType, Repository, Tree};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tauri::{command, State};
+
+use crate::lfs::{parse_pointer, LfsPointer};
+use crate::{resolve_revision, RepositoryState};
+
+// Blobs larger than this are reported without their content unless the caller asks for more
+const DEFAULT_MAX_BLOB_SIZE: usize = 2 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileContent {
+    path: String,
+    oid: String,
+    size: usize,
+    is_binary: bool,
+    too_large: bool,
+    lfs: Option<LfsPointer>,
+    content: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TreeEntryInfo {
+    name: String,
+    path: String,
+    oid: String,
+    #[serde(rename = "type")]
+    entry_type: String,
+    mode: String,
+    size: Option<usize>,
+}
+
+#[command]
+pub async fn get_file_at_revision(
+    path: String,
+    rev: String,
+    max_size: Option<usize>,
+    state: State<'_, RepositoryState>,
+) -> Result<FileContent, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let tree = resolve_tree(repo, &rev)?;
+    let entry = tree
+        .get_path(Path::new(&path))
+        .map_err(|_| format!("'{}' does not exist at {}", path, rev))?;
+    if entry.kind() != Some(ObjectType::Blob) {
+        return Err(format!("'{}' is not a file at {}", path, rev));
+    }
+
+    // The header gives the size without inflating the object, so huge blobs are never loaded
+    let (size, _) = repo
+        .odb()
+        .and_then(|odb| odb.read_header(entry.id()))
+        .map_err(|e| e.to_string())?;
+    if size > max_size.unwrap_or(DEFAULT_MAX_BLOB_SIZE) {
+        // Pointer files are tiny, so an oversized blob is never LFS; binary-ness is left unchecked
+        return Ok(FileContent {
+            path,
+            oid: entry.id().to_string(),
+            size,
+            is_binary: false,
+            too_large: true,
+            lfs: None,
+            content: None,
+        });
+    }
+
+    let blob = repo.find_blob(entry.id()).map_err(|e| e.to_string())?;
+    // The pointer text is meaningless to show, the UI describes the LFS object instead
+    let lfs = parse_pointer(blob.content());
+
+    // UTF-16 text is full of NUL bytes and would look binary, so a BOM is checked for first
+    let bom = Encoding::for_bom(blob.content());
+    let is_binary = bom.is_none() && blob.is_binary();
+    let content = if is_binary || lfs.is_some() {
+        None
+    } else {
+        let encoding = bom.map(|(encoding, _)| encoding).unwrap_or(UTF_8);
+        let (text, _, _) = encoding.decode(blob.content());
+        Some(text.into_owned())
+    };
+
+    Ok(FileContent {
+        path,
+        oid: blob.id().to_string(),
+        size,
+        is_binary,
+        too_large: false,
+        lfs,
+        content,
+    })
+}
+
+#[command]
+pub async fn list_tree(
+    rev: String,
+    path: Option<String>,
+    state: State<'_, RepositoryState>,
+) -> Result<Vec<TreeEntryInfo>, String> {
+    let repo = state.0.lock().unwrap();
+    let repo = repo.as_ref().ok_or("No repository opened")?;
+
+    let root = resolve_tree(repo, &rev)?;
+    let prefix = path
+        .as_deref()
+        .map(|p| p.trim_matches('/'))
+        .unwrap_or("")
+        .to_string();
+
+    let tree = if prefix.is_empty() {
+        root
+    } else {
+        root.get_path(Path::new(&prefix))
+            .and_then(|entry| entry.to_object(repo))
+            .and_then(|object| object.peel_to_tree())
+            .map_err(|_| format!("'{}' is not a directory at {}", prefix, rev))?
+    };
+
+    // Reading object headers gives blob sizes without inflating every file
+    let odb = repo.odb().map_err(|e| e.to_string())?;
+    let mut entries: Vec<TreeEntryInfo> = tree
+        .iter()
+        .map(|entry| {
+            let name = entry.name().unwrap_or("").to_string();
+            let entry_type = match entry.kind() {
+                Some(ObjectType::Tree) => "tree",
+                Some(ObjectType::Blob) => "blob",
+                // Gitlinks point at a commit in another repository
+                Some(ObjectType::Commit) => "submodule",
+                _ => "unknown",
+            };
+            let size = match entry.kind() {
+                Some(ObjectType::Blob) => odb.read_header(entry.id()).ok().map(|(size, _)| size),
+                _ => None,
+            };
+
+            TreeEntryInfo {
+                path: if prefix.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}/{}", prefix, name)
+                },
+                name,
+                oid: entry.id().to_string(),
+                entry_type: entry_type.to_string(),
+                mode: format!("{:06o}", entry.filemode()),
+                size,
+            }
+        })
+        .collect();
+
+    // Directories first, then alphabetical like most file browsers
+    entries.sort_by(|a, b| {
+        (a.entry_type != "tree")
+            .cmp(&(b.entry_type != "tree"))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+
+    Ok(entries)
+}
+
+pub fn resolve_tree<'r>(repo: &'r Repository, rev: &str) -> Result<Tree<'r>, String> {
+    resolve_revision(repo, rev)?
+        .peel_to_tree()
+        .map_err(|e| format!("Revision '{}' has no tree: {}", rev, e.message()))
+}
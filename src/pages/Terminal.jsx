@@ -39,32 +39,47 @@ const TerminalInner = dynamic(() => Promise.resolve(() => {
       // Open terminal
       terminal.open(terminalRef.current);
 
+      // Listen for PTY output before the shell starts so the prompt isn't missed. Output can
+      // arrive before create_pty resolves with our id, so it is held until the id is known.
+      let sessionId = null;
+      let pending = [];
+      const unsubscribe = await listen('pty-output', (event) => {
+        if (sessionId === null) {
+          pending.push(event.payload);
+        } else if (terminal.element && event.payload.session_id === sessionId) {
+          terminal.write(event.payload.data);
+        }
+      });
+
+      // Initialize PTY
+      try {
+        sessionId = await invoke('create_pty', { rows: terminal.rows, cols: terminal.cols });
+      } catch (error) {
+        unsubscribe();
+        terminal.dispose();
+        throw error;
+      }
+      pending
+        .filter(payload => payload.session_id === sessionId)
+        .forEach(payload => terminal.write(payload.data));
+      pending = [];
+
       // Initial fit
       setTimeout(() => {
         if (fitAddon) {
           try {
             fitAddon.fit();
             const { rows, cols } = terminal;
-            invoke('resize_pty', { rows, cols }).catch(console.error);
+            invoke('resize_pty', { sessionId, rows, cols }).catch(console.error);
           } catch (error) {
             console.error('Fit error:', error);
           }
         }
       }, 100);
 
-      // Initialize PTY
-      invoke('create_pty').catch(console.error);
-
-      // Listen for PTY output
-      const unsubscribe = await listen('pty-output', (event) => {
-        if (terminal.element) {
-          terminal.write(event.payload);
-        }
-      });
-
       // Handle input
       terminal.onData(data => {
-        invoke('write_to_pty', { input: data }).catch(console.error);
+        invoke('write_to_pty', { sessionId, input: data }).catch(console.error);
       });
 
       // Handle resize
@@ -79,7 +94,7 @@ const TerminalInner = dynamic(() => Promise.resolve(() => {
             try {
               fitAddon.fit();
               const { rows, cols } = terminal;
-              invoke('resize_pty', { rows, cols }).catch(console.error);
+              invoke('resize_pty', { sessionId, rows, cols }).catch(console.error);
             } catch (error) {
               console.error('Resize error:', error);
             }
@@ -94,7 +109,8 @@ const TerminalInner = dynamic(() => Promise.resolve(() => {
         if (resizeTimeout) {
           clearTimeout(resizeTimeout);
         }
-        unsubscribe.then(fn => fn());
+        unsubscribe();
+        invoke('close_pty', { sessionId }).catch(console.error);
         resizeObserver.disconnect();
         if (terminal) {
           terminal.dispose();
@@ -102,7 +118,24 @@ const TerminalInner = dynamic(() => Promise.resolve(() => {
       };
     };
 
-    initializeTerminal().catch(console.error);
+    // initializeTerminal is async, so its cleanup only exists once it resolves. If the
+    // component unmounts first, the cleanup runs as soon as it is available.
+    let disposed = false;
+    let cleanup = null;
+    initializeTerminal()
+      .then(fn => {
+        if (disposed) {
+          if (fn) fn();
+        } else {
+          cleanup = fn;
+        }
+      })
+      .catch(console.error);
+
+    return () => {
+      disposed = true;
+      if (cleanup) cleanup();
+    };
   }, []);
 
   return (